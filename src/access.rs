@@ -0,0 +1,63 @@
+//! Access markers
+//!
+//!Specifies which operations are permitted on memory map at compile time.
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+///Describes access to memory map
+pub trait Access: sealed::Sealed {
+    #[doc(hidden)]
+    ///Short name of permitted operations
+    const NAME: &'static str;
+    #[doc(hidden)]
+    ///Whether reading is permitted
+    const READABLE: bool;
+    #[doc(hidden)]
+    ///Whether writing is permitted
+    const WRITABLE: bool;
+}
+
+///Describes access that permits reading
+pub trait Readable: Access {
+}
+
+///Describes access that permits writing
+pub trait Writable: Access {
+}
+
+///Read only access
+pub struct ReadOnly;
+
+///Write only access
+pub struct WriteOnly;
+
+///Read and write access
+pub struct ReadWrite;
+
+impl sealed::Sealed for ReadOnly {}
+impl sealed::Sealed for WriteOnly {}
+impl sealed::Sealed for ReadWrite {}
+
+impl Access for ReadOnly {
+    const NAME: &'static str = "R";
+    const READABLE: bool = true;
+    const WRITABLE: bool = false;
+}
+impl Access for WriteOnly {
+    const NAME: &'static str = "W";
+    const READABLE: bool = false;
+    const WRITABLE: bool = true;
+}
+impl Access for ReadWrite {
+    const NAME: &'static str = "RW";
+    const READABLE: bool = true;
+    const WRITABLE: bool = true;
+}
+
+impl Readable for ReadOnly {}
+impl Readable for ReadWrite {}
+
+impl Writable for WriteOnly {}
+impl Writable for ReadWrite {}
@@ -0,0 +1,54 @@
+//! Memory usage advice
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Advice about expected usage of memory, given to kernel via `madvise`
+pub enum Advice {
+    ///No special treatment
+    Normal,
+    ///Pages are going to be accessed in random order, making read ahead less useful
+    Random,
+    ///Pages are going to be accessed in sequential order, making aggressive read ahead useful
+    Sequential,
+    ///Pages are going to be accessed soon, hence it is useful to read them ahead
+    WillNeed,
+    ///Pages are not going to be accessed soon, hence kernel may free associated resources
+    ///
+    ///Note that on linux subsequent access to private mapping results in zero filled pages
+    ///or pages re-read from file.
+    DontNeed,
+    ///Pages should be backed by transparent huge pages
+    ///
+    ///Linux only. Works only with anonymous private mappings unless kernel supports file backed huge pages.
+    HugePage,
+    ///Pages should not be backed by transparent huge pages
+    ///
+    ///Linux only.
+    NoHugePage,
+    ///Pages should be excluded from core dump
+    ///
+    ///Linux only.
+    DontDump,
+}
+
+impl Advice {
+    #[cfg(unix)]
+    #[inline]
+    ///Returns `madvise` value, if advice is supported by platform.
+    pub(crate) const fn as_raw(self) -> Option<libc::c_int> {
+        match self {
+            Advice::Normal => Some(libc::MADV_NORMAL),
+            Advice::Random => Some(libc::MADV_RANDOM),
+            Advice::Sequential => Some(libc::MADV_SEQUENTIAL),
+            Advice::WillNeed => Some(libc::MADV_WILLNEED),
+            Advice::DontNeed => Some(libc::MADV_DONTNEED),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Advice::HugePage => Some(libc::MADV_HUGEPAGE),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Advice::NoHugePage => Some(libc::MADV_NOHUGEPAGE),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Advice::DontDump => Some(libc::MADV_DONTDUMP),
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            Advice::HugePage | Advice::NoHugePage | Advice::DontDump => None,
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Atomic access
+
+use core::sync::atomic::{self, Ordering};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+///Read-modify-write operation
+pub enum FetchOp {
+    Add,
+    Sub,
+    And,
+    Or,
+    Xor,
+    Swap,
+}
+
+///Integer type, which can be accessed atomically
+pub trait Atomic: sealed::Sealed + Copy {
+    #[doc(hidden)]
+    unsafe fn load(ptr: *mut Self, order: Ordering) -> Self;
+    #[doc(hidden)]
+    unsafe fn store(ptr: *mut Self, val: Self, order: Ordering);
+    #[doc(hidden)]
+    unsafe fn compare_exchange(ptr: *mut Self, current: Self, new: Self, success: Ordering, failure: Ordering) -> Result<Self, Self>;
+    #[doc(hidden)]
+    ///Performs `op` with `val`, returning previous and new value
+    unsafe fn fetch(ptr: *mut Self, op: FetchOp, val: Self, order: Ordering) -> (Self, Self);
+}
+
+macro_rules! impl_atomic {
+    ($($size:literal: $ty:ty => $atomic:ident,)*) => {
+        $(
+            #[cfg(target_has_atomic = $size)]
+            impl sealed::Sealed for $ty {}
+
+            #[cfg(target_has_atomic = $size)]
+            impl Atomic for $ty {
+                #[inline(always)]
+                unsafe fn load(ptr: *mut Self, order: Ordering) -> Self {
+                    assert!(ptr.cast::<atomic::$atomic>().is_aligned(), "pointer is misaligned for {}", stringify!($atomic));
+                    atomic::$atomic::from_ptr(ptr).load(order)
+                }
+
+                #[inline(always)]
+                unsafe fn store(ptr: *mut Self, val: Self, order: Ordering) {
+                    assert!(ptr.cast::<atomic::$atomic>().is_aligned(), "pointer is misaligned for {}", stringify!($atomic));
+                    atomic::$atomic::from_ptr(ptr).store(val, order)
+                }
+
+                #[inline(always)]
+                unsafe fn compare_exchange(ptr: *mut Self, current: Self, new: Self, success: Ordering, failure: Ordering) -> Result<Self, Self> {
+                    assert!(ptr.cast::<atomic::$atomic>().is_aligned(), "pointer is misaligned for {}", stringify!($atomic));
+                    atomic::$atomic::from_ptr(ptr).compare_exchange(current, new, success, failure)
+                }
+
+                #[inline(always)]
+                unsafe fn fetch(ptr: *mut Self, op: FetchOp, val: Self, order: Ordering) -> (Self, Self) {
+                    assert!(ptr.cast::<atomic::$atomic>().is_aligned(), "pointer is misaligned for {}", stringify!($atomic));
+                    let atomic = atomic::$atomic::from_ptr(ptr);
+                    match op {
+                        FetchOp::Add => {
+                            let prev = atomic.fetch_add(val, order);
+                            (prev, prev.wrapping_add(val))
+                        },
+                        FetchOp::Sub => {
+                            let prev = atomic.fetch_sub(val, order);
+                            (prev, prev.wrapping_sub(val))
+                        },
+                        FetchOp::And => {
+                            let prev = atomic.fetch_and(val, order);
+                            (prev, prev & val)
+                        },
+                        FetchOp::Or => {
+                            let prev = atomic.fetch_or(val, order);
+                            (prev, prev | val)
+                        },
+                        FetchOp::Xor => {
+                            let prev = atomic.fetch_xor(val, order);
+                            (prev, prev ^ val)
+                        },
+                        FetchOp::Swap => (atomic.swap(val, order), val),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_atomic!(
+    "8": u8 => AtomicU8,
+    "16": u16 => AtomicU16,
+    "32": u32 => AtomicU32,
+    "64": u64 => AtomicU64,
+    "ptr": usize => AtomicUsize,
+    "8": i8 => AtomicI8,
+    "16": i16 => AtomicI16,
+    "32": i32 => AtomicI32,
+    "64": i64 => AtomicI64,
+    "ptr": isize => AtomicIsize,
+);
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::Ordering;
+
+    use crate::MemoryMap;
+
+    #[test]
+    fn should_access_aligned_data() {
+        let mut data = 0u64;
+        let mut map = unsafe {
+            MemoryMap::<u64>::from_ptr(&mut data)
+        };
+
+        map.store_atomic(1, Ordering::Release);
+        assert_eq!(map.fetch_add(2, Ordering::AcqRel), 1);
+        assert_eq!(map.compare_exchange(3, 4, Ordering::AcqRel, Ordering::Acquire), Ok(3));
+        assert_eq!(map.load_atomic(Ordering::Acquire), 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_panic_on_misaligned_load() {
+        let mut data = [0u64; 2];
+        let map = unsafe {
+            MemoryMap::<u32>::from_ptr((data.as_mut_ptr() as *mut u8).add(1) as *mut u32)
+        };
+
+        map.load_atomic(Ordering::Acquire);
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_panic_on_misaligned_fetch() {
+        let mut data = [0u64; 2];
+        let mut map = unsafe {
+            MemoryMap::<u32>::from_ptr((data.as_mut_ptr() as *mut u8).add(2) as *mut u32)
+        };
+
+        map.fetch_add(1, Ordering::AcqRel);
+    }
+
+    #[cfg(all(feature = "mmap", target_has_atomic = "64"))]
+    #[test]
+    fn should_increment_shared_counter_from_threads() {
+        const THREADS: u64 = 4;
+        const ROUNDS: u64 = 1000;
+
+        let map = MemoryMap::<u64>::try_anonymous().expect("to map");
+        let address = map.as_ptr() as usize;
+        let threads = (0..THREADS).map(|_| std::thread::spawn(move || {
+            let mut counter = unsafe {
+                MemoryMap::<u64>::from_ptr(address as *mut u64)
+            };
+            for _ in 0..ROUNDS {
+                let mut current = counter.load_atomic(Ordering::Relaxed);
+                while let Err(actual) = counter.compare_exchange(current, current + 1, Ordering::AcqRel, Ordering::Relaxed) {
+                    current = actual;
+                }
+                counter.fetch_add(1, Ordering::AcqRel);
+            }
+        })).collect::<std::vec::Vec<_>>();
+
+        for thread in threads {
+            thread.join().expect("to finish");
+        }
+        assert_eq!(map.load_atomic(Ordering::Acquire), THREADS * ROUNDS * 2);
+    }
+
+    #[cfg(all(feature = "mmap", unix))]
+    #[test]
+    fn should_increment_counter_shared_with_child_process() {
+        const ROUNDS: u32 = 10_000;
+
+        let mut counter = MemoryMap::<u32>::try_anonymous().expect("to map");
+        let hammer = |counter: &mut MemoryMap<u32>| {
+            for _ in 0..ROUNDS {
+                counter.fetch_add(1, Ordering::AcqRel);
+            }
+        };
+
+        match unsafe { libc::fork() } {
+            -1 => panic!("fork failed"),
+            0 => {
+                hammer(&mut counter);
+                unsafe {
+                    libc::_exit(0)
+                }
+            },
+            child => {
+                hammer(&mut counter);
+                let mut status = 0;
+                assert_eq!(unsafe { libc::waitpid(child, &mut status, 0) }, child);
+                assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+            },
+        }
+        assert_eq!(counter.load_atomic(Ordering::Acquire), ROUNDS * 2);
+    }
+}
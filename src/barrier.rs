@@ -0,0 +1,79 @@
+//! Memory barriers
+//!
+//!Volatile accesses are ordered with respect to each other, but not with respect to accesses to normal memory,
+//!neither by compiler nor by CPU. Barriers are emitted per architecture, falling back to atomic fences elsewhere,
+//!including `arm`, where barrier instructions depend on architecture version, which cannot be detected on stable compiler.
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64", target_arch = "riscv32", target_arch = "riscv64"))]
+use core::arch::asm;
+
+#[inline(always)]
+///Orders all memory accesses before barrier against all memory accesses after it, including MMIO.
+///
+///It is `mfence` on `x86`, `dsb sy` on `aarch64` and `fence iorw, iorw` on `riscv`.
+///Elsewhere it is sequentially consistent fence.
+pub fn barrier() {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    unsafe {
+        asm!("mfence", options(nostack, preserves_flags));
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        asm!("dsb sy", options(nostack, preserves_flags));
+    }
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe {
+        asm!("fence iorw, iorw", options(nostack, preserves_flags));
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64", target_arch = "riscv32", target_arch = "riscv64")))]
+    core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+}
+
+#[inline(always)]
+///Orders writes to normal memory before barrier against MMIO write after it.
+///
+///Compiler barrier on `x86`, where such writes are not reordered by CPU.
+///It is `dmb oshst` on `aarch64` and `fence w, o` on `riscv`.
+///Elsewhere it is release fence.
+pub(crate) fn write() {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    unsafe {
+        asm!("", options(nostack, preserves_flags));
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        asm!("dmb oshst", options(nostack, preserves_flags));
+    }
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe {
+        asm!("fence w, o", options(nostack, preserves_flags));
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64", target_arch = "riscv32", target_arch = "riscv64")))]
+    core::sync::atomic::fence(core::sync::atomic::Ordering::Release);
+}
+
+#[inline(always)]
+///Orders MMIO read before barrier against reads of normal memory after it.
+///
+///Compiler barrier on `x86`, where such reads are not reordered by CPU.
+///It is `dmb oshld` on `aarch64` and `fence i, r` on `riscv`.
+///Elsewhere it is acquire fence.
+pub(crate) fn read() {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    unsafe {
+        asm!("", options(nostack, preserves_flags));
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        asm!("dmb oshld", options(nostack, preserves_flags));
+    }
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    unsafe {
+        asm!("fence i, r", options(nostack, preserves_flags));
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64", target_arch = "riscv32", target_arch = "riscv64")))]
+    core::sync::atomic::fence(core::sync::atomic::Ordering::Acquire);
+}
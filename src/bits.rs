@@ -0,0 +1,27 @@
+//! Bit manipulation
+
+use core::ops;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+///Integer type, bits of which can be manipulated
+pub trait Bits: sealed::Sealed + Copy + PartialEq + ops::BitOr<Output = Self> + ops::BitAnd<Output = Self> + ops::BitXor<Output = Self> + ops::Not<Output = Self> {
+    #[doc(hidden)]
+    ///Value with no bits set
+    const ZERO: Self;
+}
+
+macro_rules! impl_bits {
+    ($($ty:ty),*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+            impl Bits for $ty {
+                const ZERO: Self = 0;
+            }
+        )*
+    };
+}
+
+impl_bits!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
@@ -0,0 +1,125 @@
+//! Block of registers at byte offsets
+
+use core::{fmt, mem};
+
+use crate::{volatile, Error, ErrorKind, MemoryMapSlice, RawPtr};
+use crate::access::{self, ReadWrite};
+
+///Untyped block of registers, accessed by byte offset
+///
+///Unlike [MemoryMap](struct.MemoryMap.html) it does not require to describe layout of peripheral as type,
+///instead each access specifies type of register at its offset.
+///
+///Every access is checked against length of block and alignment of register type,
+///returning `TooSmall` error if register does not fit within block and `Misaligned` error if offset is not aligned for it.
+pub struct RegisterBlock<A = ReadWrite> {
+    region: MemoryMapSlice<u8, A>,
+}
+
+impl<A: access::Access> RegisterBlock<A> {
+    #[inline]
+    ///Creates block over bytes of `region`
+    pub const fn new(region: MemoryMapSlice<u8, A>) -> Self {
+        Self {
+            region,
+        }
+    }
+
+    #[inline]
+    ///Returns underlying region.
+    pub fn into_inner(self) -> MemoryMapSlice<u8, A> {
+        self.region
+    }
+
+    #[inline]
+    ///Returns number of bytes within block.
+    pub const fn len(&self) -> usize {
+        self.region.len()
+    }
+
+    #[inline]
+    ///Returns whether block has no bytes.
+    pub const fn is_empty(&self) -> bool {
+        self.region.is_empty()
+    }
+
+    #[inline]
+    fn check<T>(&self, offset: usize) -> Result<*mut T, Error> {
+        match offset.checked_add(mem::size_of::<T>()) {
+            Some(end) if end <= self.region.len() => (),
+            _ => return Err(Error::new(ErrorKind::TooSmall, 0)),
+        }
+
+        let ptr = unsafe {
+            self.region.as_ptr().add(offset) as *mut T
+        };
+        match ptr.is_aligned() {
+            true => Ok(ptr),
+            false => Err(Error::new(ErrorKind::Misaligned, 0)),
+        }
+    }
+
+    #[inline]
+    ///Access register of type `T` at `offset`
+    pub fn reg<T>(&mut self, offset: usize) -> Result<RawPtr<'_, T, A>, Error> {
+        let ptr = self.check::<T>(offset)?;
+        Ok(RawPtr::new(ptr))
+    }
+}
+
+impl<A: access::Readable> RegisterBlock<A> {
+    #[inline]
+    ///Reads register of type `T` at `offset`
+    pub fn read_at<T: Copy>(&self, offset: usize) -> Result<T, Error> {
+        let ptr = self.check::<T>(offset)?;
+        Ok(unsafe {
+            volatile::read(ptr)
+        })
+    }
+}
+
+impl<A: access::Writable> RegisterBlock<A> {
+    #[inline]
+    ///Writes register of type `T` at `offset`
+    pub fn write_at<T>(&mut self, offset: usize, val: T) -> Result<(), Error> {
+        let ptr = self.check::<T>(offset)?;
+        unsafe {
+            volatile::write(ptr, val)
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl RegisterBlock {
+    #[inline]
+    ///Creates block of `len` bytes over anonymous memory mapping
+    pub fn anonymous(len: usize) -> Result<Self, Error> {
+        MemoryMapSlice::anonymous(len).map(Self::new)
+    }
+
+    #[inline]
+    ///Creates block of `len` bytes on `/dev/mem` which accesses physical memory
+    ///
+    ///## Arguments
+    ///
+    ///- `offset` - Offset within memory to start.
+    ///- `len` - Number of bytes to map.
+    pub unsafe fn dev_mem(offset: libc::off_t, len: usize) -> Result<Self, Error> {
+        MemoryMapSlice::dev_mem(offset, len).map(Self::new)
+    }
+}
+
+impl<A: access::Access> From<MemoryMapSlice<u8, A>> for RegisterBlock<A> {
+    #[inline(always)]
+    fn from(region: MemoryMapSlice<u8, A>) -> Self {
+        Self::new(region)
+    }
+}
+
+impl<A> fmt::Debug for RegisterBlock<A> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RegisterBlock").field("region", &self.region).finish()
+    }
+}
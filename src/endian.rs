@@ -0,0 +1,143 @@
+//! Byte order of data
+
+use core::fmt;
+
+use crate::{access, MemoryMap};
+
+#[repr(transparent)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+///Integer stored in little endian byte order, regardless of platform
+///
+///Memory map of `Le<T>` reads and writes data in little endian, while `read_native` and `write_native` convert value from and to native byte order:
+///
+///```
+///use dev_mmio::{MemoryMap, Le};
+///
+///let mut map = MemoryMap::<Le<u32>>::try_anonymous().expect("to map");
+///map.write_native(0x1234_5678);
+///assert_eq!(map.read_native(), 0x1234_5678);
+///assert_eq!(u32::from(map.read()), 0x1234_5678);
+///assert_eq!(unsafe { map.read_at::<[u8; 4]>(0) }, [0x78, 0x56, 0x34, 0x12]);
+///```
+pub struct Le<T>(T);
+
+#[repr(transparent)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+///Integer stored in big endian byte order, regardless of platform
+///
+///Memory map of `Be<T>` reads and writes data in big endian, while `read_native` and `write_native` convert value from and to native byte order:
+///
+///```
+///use dev_mmio::{MemoryMap, Be};
+///
+///let mut map = MemoryMap::<Be<u32>>::try_anonymous().expect("to map");
+///map.write_native(0x1234_5678);
+///assert_eq!(map.read_native(), 0x1234_5678);
+///assert_eq!(u32::from(map.read()), 0x1234_5678);
+///assert_eq!(unsafe { map.read_at::<[u8; 4]>(0) }, [0x12, 0x34, 0x56, 0x78]);
+///```
+pub struct Be<T>(T);
+
+macro_rules! impl_endian {
+    ($($ty:ident),*) => {
+        $(
+            impl_endian!(Le: $ty, to_le, from_le);
+            impl_endian!(Be: $ty, to_be, from_be);
+        )*
+    };
+    ($name:ident: $ty:ident, $to:ident, $from:ident) => {
+        impl $name<$ty> {
+            #[inline(always)]
+            ///Creates from value in native byte order
+            pub const fn new(val: $ty) -> Self {
+                Self(val.$to())
+            }
+
+            #[inline(always)]
+            ///Returns value in native byte order
+            pub const fn get(self) -> $ty {
+                $ty::$from(self.0)
+            }
+
+            #[inline(always)]
+            ///Creates from value as it is stored in memory
+            pub const fn from_raw(raw: $ty) -> Self {
+                Self(raw)
+            }
+
+            #[inline(always)]
+            ///Returns value as it is stored in memory
+            pub const fn to_raw(self) -> $ty {
+                self.0
+            }
+        }
+
+        impl From<$ty> for $name<$ty> {
+            #[inline(always)]
+            fn from(val: $ty) -> Self {
+                Self::new(val)
+            }
+        }
+
+        impl From<$name<$ty>> for $ty {
+            #[inline(always)]
+            fn from(val: $name<$ty>) -> Self {
+                val.get()
+            }
+        }
+
+        impl fmt::Debug for $name<$ty> {
+            #[inline(always)]
+            fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.get(), fmt)
+            }
+        }
+
+        impl<A: access::Readable> MemoryMap<$name<$ty>, A> {
+            #[inline]
+            ///Reads value, converting it to native byte order
+            pub fn read_native(&self) -> $ty {
+                self.read().get()
+            }
+        }
+
+        impl<A: access::Writable> MemoryMap<$name<$ty>, A> {
+            #[inline]
+            ///Writes value in native byte order, converting it to byte order of memory
+            pub fn write_native(&mut self, val: $ty) {
+                self.write($name::<$ty>::new(val))
+            }
+        }
+    };
+}
+
+impl_endian!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+#[cfg(test)]
+mod tests {
+    use super::{Be, Le};
+    use crate::MemoryMap;
+
+    #[test]
+    fn should_store_in_byte_order_of_wrapper() {
+        let mut data = 0u32;
+        let mut map = unsafe {
+            MemoryMap::<Be<u32>>::from_ptr(&mut data as *mut u32 as *mut Be<u32>)
+        };
+        map.write_native(0x1234_5678);
+        assert_eq!(map.read_native(), 0x1234_5678);
+        assert_eq!(map.read().to_raw(), u32::from_ne_bytes([0x12, 0x34, 0x56, 0x78]));
+        assert_eq!(data.to_ne_bytes(), [0x12, 0x34, 0x56, 0x78]);
+
+        let mut map = unsafe {
+            MemoryMap::<Le<u32>>::from_ptr(&mut data as *mut u32 as *mut Le<u32>)
+        };
+        map.write_native(0x1234_5678);
+        assert_eq!(map.read_native(), 0x1234_5678);
+        assert_eq!(data.to_ne_bytes(), [0x78, 0x56, 0x34, 0x12]);
+
+        //Raw value is stored as it is
+        map.write(Le::<u32>::from_raw(u32::from_ne_bytes([0x21, 0x43, 0x65, 0x87])));
+        assert_eq!(map.read_native(), 0x8765_4321);
+    }
+}
@@ -0,0 +1,177 @@
+//! Error type
+
+use core::{ffi, fmt};
+
+#[cfg(all(feature = "mmap", unix))]
+use crate::unix as sys;
+#[cfg(all(feature = "mmap", windows))]
+use crate::win32 as sys;
+
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Describes which operation failed
+pub enum ErrorKind {
+    ///Failed to open file
+    OpenFailed,
+    ///Device is not found
+    NotFound,
+    ///Permission to access device is denied
+    PermissionDenied,
+    ///Resource cannot be memory mapped
+    NotMappable,
+    ///Device attribute cannot be read or parsed
+    InvalidAttribute,
+    ///Failed to map memory
+    MapFailed,
+    ///No huge pages of requested size are available
+    NoHugePages,
+    ///Failed to resize backing object
+    ResizeFailed,
+    ///Failed to unlink named object
+    UnlinkFailed,
+    ///Failed to unmap memory
+    UnmapFailed,
+    ///Backing object is too small to fit mapping
+    TooSmall,
+    ///Offset is misaligned for mapped type
+    Misaligned,
+    ///Failed to flush changes to backing object
+    FlushFailed,
+    ///Failed to change memory protection
+    ProtectFailed,
+    ///Failed to lock or unlock memory
+    LockFailed,
+    ///Failed to give advice about memory usage
+    AdviseFailed,
+    ///Failed to access IO port
+    PortFailed,
+    ///Condition is not met within allowed number of attempts
+    TimedOut,
+    ///Operation is not supported on this platform
+    Unsupported,
+    ///Options cannot be combined
+    InvalidOptions,
+}
+
+impl ErrorKind {
+    #[inline]
+    const fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::OpenFailed => "Failed to open file",
+            ErrorKind::NotFound => "Device is not found",
+            ErrorKind::PermissionDenied => "Permission to access device is denied",
+            ErrorKind::NotMappable => "Resource cannot be memory mapped",
+            ErrorKind::InvalidAttribute => "Device attribute cannot be read or parsed",
+            ErrorKind::MapFailed => "Failed to map memory",
+            ErrorKind::NoHugePages => "No huge pages of requested size are available",
+            ErrorKind::ResizeFailed => "Failed to resize backing object",
+            ErrorKind::UnlinkFailed => "Failed to unlink named object",
+            ErrorKind::UnmapFailed => "Failed to unmap memory",
+            ErrorKind::TooSmall => "Backing object is too small to fit mapping",
+            ErrorKind::Misaligned => "Offset is misaligned for mapped type",
+            ErrorKind::FlushFailed => "Failed to flush changes to backing object",
+            ErrorKind::ProtectFailed => "Failed to change memory protection",
+            ErrorKind::LockFailed => "Failed to lock or unlock memory",
+            ErrorKind::AdviseFailed => "Failed to give advice about memory usage",
+            ErrorKind::PortFailed => "Failed to access IO port",
+            ErrorKind::TimedOut => "Condition is not met within allowed number of attempts",
+            ErrorKind::Unsupported => "Operation is not supported",
+            ErrorKind::InvalidOptions => "Options cannot be combined",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Memory map error
+pub struct Error {
+    kind: ErrorKind,
+    code: ffi::c_int,
+}
+
+impl Error {
+    #[inline]
+    ///Creates new error with raw OS error code.
+    pub const fn new(kind: ErrorKind, code: ffi::c_int) -> Self {
+        Self {
+            kind,
+            code,
+        }
+    }
+
+    #[inline]
+    ///Creates error of `Unsupported` kind.
+    pub const fn unsupported() -> Self {
+        Self::new(ErrorKind::Unsupported, 0)
+    }
+
+    #[cfg(all(feature = "mmap", any(unix, windows)))]
+    #[inline]
+    ///Creates error from last OS error.
+    ///
+    ///Must be called immediately after failed call.
+    pub(crate) fn last(kind: ErrorKind) -> Self {
+        Self::new(kind, sys::errno())
+    }
+
+    #[cfg(all(feature = "mmap", any(unix, windows)))]
+    #[inline]
+    ///Creates error indicating that requested length cannot be mapped.
+    pub(crate) const fn too_large() -> Self {
+        Self::new(ErrorKind::MapFailed, sys::ENOMEM)
+    }
+
+    #[inline]
+    ///Returns which operation failed
+    pub const fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    #[inline]
+    ///Returns raw OS error code, which is `errno` on unix and `GetLastError` on windows.
+    ///
+    ///Zero if there is no OS error.
+    pub const fn raw_os_error(&self) -> ffi::c_int {
+        self.code
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(self.kind.as_str())?;
+
+        if self.code == 0 {
+            return Ok(());
+        }
+
+        #[cfg(all(feature = "mmap", any(unix, windows)))]
+        if let Some(desc) = sys::describe(self.code) {
+            return fmt.write_fmt(format_args!(": {} (os error {})", desc, self.code));
+        }
+
+        fmt.write_fmt(format_args!(": os error {}", self.code))
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(error: Error) -> Self {
+        let kind = match error.kind {
+            ErrorKind::Unsupported => std::io::ErrorKind::Unsupported,
+            ErrorKind::TooSmall => std::io::ErrorKind::UnexpectedEof,
+            ErrorKind::Misaligned => std::io::ErrorKind::InvalidInput,
+            ErrorKind::NotMappable => std::io::ErrorKind::InvalidInput,
+            ErrorKind::InvalidOptions => std::io::ErrorKind::InvalidInput,
+            ErrorKind::TimedOut => std::io::ErrorKind::TimedOut,
+            _ => match error.code {
+                0 => std::io::ErrorKind::Other,
+                code => std::io::Error::from_raw_os_error(code).kind(),
+            },
+        };
+
+        std::io::Error::new(kind, error)
+    }
+}
@@ -0,0 +1,30 @@
+//! Huge pages
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Size of huge page backing memory mapping
+pub enum HugePageSize {
+    ///2 MiB page
+    Size2MiB,
+    ///1 GiB page
+    Size1GiB,
+}
+
+impl HugePageSize {
+    #[inline]
+    ///Returns size of page in bytes.
+    pub const fn bytes(self) -> usize {
+        match self {
+            HugePageSize::Size2MiB => 2 * 1024 * 1024,
+            HugePageSize::Size1GiB => 1024 * 1024 * 1024,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub(crate) const fn as_raw(self) -> libc::c_int {
+        match self {
+            HugePageSize::Size2MiB => libc::MAP_HUGE_2MB,
+            HugePageSize::Size1GiB => libc::MAP_HUGE_1GB,
+        }
+    }
+}
@@ -1,174 +1,1195 @@
 //! Memory mapped IO
+//!
+//! ## Features
+//!
+//! - `mmap` - Enables creation of memory maps via OS facilities, requiring `libc`. Enabled by default.
+//!
+//!   Without it, crate can be used on bare metal targets via [MemoryMap::from_ptr](struct.MemoryMap.html#method.from_ptr).
+//! - `std` - Enables integration with `std::fs::File`, `std::path::Path` and `std::io::Error`.
+//! - `trace` - Enables [tracing](trace/index.html) of memory accesses via global hook.
+//! - `mock` - Enables [mock](mock/index.html) memory for testing drivers without hardware. Implies `std`.
+//! - `macros` - Enables [register_block](macro.register_block.html) macro, declaring registers with getters and setters according to their access.
 
 #![no_std]
 #![warn(missing_docs)]
-#![cfg_attr(feature = "cargo-clippy", allow(clippy::style))]
+#![allow(clippy::style)]
 
-use core::{fmt, ptr, marker};
+#[cfg(any(test, feature = "std"))]
+extern crate std;
 
-///Memory mapped raw pointer
-pub struct RawPtr<'a, T> {
-    ///Pointer
-    pub ptr: *mut T,
-    _lifetime: marker::PhantomData<&'a mut T>,
-}
+use core::{fmt, mem, ptr, marker};
+use core::sync::atomic::Ordering;
+
+#[cfg(all(feature = "mmap", unix))]
+mod unix;
+#[cfg(all(feature = "mmap", windows))]
+mod win32;
+pub mod access;
+#[cfg(feature = "mmap")]
+mod advice;
+mod atomic;
+mod barrier;
+mod bits;
+mod block;
+mod endian;
+mod error;
+#[cfg(feature = "mmap")]
+mod huge;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "mmap")]
+mod options;
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+pub mod pci;
+#[cfg(all(feature = "mmap", unix))]
+pub mod port;
+#[cfg(feature = "mmap")]
+mod protection;
+mod raw;
+mod registers;
+#[cfg(target_has_atomic = "32")]
+mod ring;
+mod slice;
+mod split;
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+mod sysfs;
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+pub mod uio;
+#[cfg(feature = "trace")]
+pub mod trace;
+mod volatile;
+
+pub use access::{ReadOnly, WriteOnly, ReadWrite};
+#[cfg(feature = "mmap")]
+pub use advice::Advice;
+pub use atomic::Atomic;
+pub use barrier::barrier;
+pub use bits::Bits;
+pub use block::RegisterBlock;
+pub use endian::{Le, Be};
+pub use error::{Error, ErrorKind};
+#[cfg(feature = "mmap")]
+pub use huge::HugePageSize;
+#[cfg(all(feature = "mmap", any(unix, windows)))]
+pub use mmap::{page_size, allocation_granularity};
+#[cfg(all(feature = "mmap", unix, not(target_os = "android")))]
+pub use mmap::unlink_named;
+#[cfg(feature = "mmap")]
+pub use options::MmapOptions;
+#[cfg(feature = "mmap")]
+pub use protection::Protection;
+pub use raw::{RawPtr, RawPtrConst, RawSlice};
+#[cfg(target_has_atomic = "32")]
+pub use ring::{RingBuffer, Producer, Consumer};
+pub use slice::{MemoryMapSlice, Iter, IterMut};
+pub use split::SplitOrder;
 
-#[repr(transparent)]
 ///Memory mapped IO
-pub struct MemoryMap<T> {
-    ptr: *mut T
+///
+///Access `A` determines whether map can be read and/or written.
+///
+///Zero sized `T` maps nothing: memory map holds dangling pointer, aligned to page size, and there is nothing to unmap on drop.
+///
+///Memory map without mapping, i.e. of zero sized `T` or created via [from_ptr](#method.from_ptr), returns `Unsupported` error
+///from operations on mapping, such as `flush`, `protect`, `lock` and `advise`, while `prefault` does nothing.
+///
+///Map is `Send` and `Sync` only if `T` is, same as pointer to `T` would be:
+///
+///```compile_fail
+///fn assert_sync<T: Sync>() {}
+///assert_sync::<dev_mmio::MemoryMap<core::cell::Cell<u32>>>();
+///```
+///
+///```compile_fail
+///fn assert_send<T: Send>() {}
+///assert_send::<dev_mmio::MemoryMap<std::rc::Rc<u32>>>();
+///```
+pub struct MemoryMap<T, A = ReadWrite> {
+    //Pointer to requested offset within mapping
+    ptr: ptr::NonNull<T>,
+    //Base address and length of mapping as returned by OS
+    base: *mut core::ffi::c_void,
+    map_len: usize,
+    //Offset requested when mapping
+    #[cfg(feature = "mmap")]
+    offset: u64,
+    //Protection of pages, as mapped or changed by protect
+    #[cfg(feature = "mmap")]
+    prot: Protection,
+    //Flags passed when mapping: mmap flags on unix and view access on windows
+    #[cfg(feature = "mmap")]
+    flags: u32,
+    //Size of pages backing mapping, which is 0 if there is no mapping
+    #[cfg(feature = "mmap")]
+    page_size: usize,
+    //File descriptor retained by mapping, closed on drop. -1 if there is none.
+    #[cfg(all(feature = "mmap", unix))]
+    fd: libc::c_int,
+    #[cfg(all(feature = "mmap", windows))]
+    handle: win32::HANDLE,
+    //Mock memory, which is kept alive by memory map
+    #[cfg(feature = "mock")]
+    mock: Option<std::sync::Arc<mock::Inner>>,
+    _access: marker::PhantomData<A>,
 }
 
-impl<T> MemoryMap<T> {
+impl<T, A: access::Readable> MemoryMap<T, A> {
     #[inline]
     ///Reads data
-    pub fn read(&self) -> T {
+    ///
+    ///It requires `T: Copy`, as every read creates new copy of data:
+    ///
+    ///```compile_fail
+    ///let map = dev_mmio::MemoryMap::<String>::try_anonymous().expect("to map");
+    ///map.read();
+    ///```
+    pub fn read(&self) -> T where T: Copy {
+        #[cfg(feature = "mock")]
+        if let Some(mock) = self.mock.as_ref() {
+            return mock.read();
+        }
+
+        unsafe {
+            volatile::read(self.ptr.as_ptr())
+        }
+    }
+
+    #[inline]
+    ///Reads data, ordering it before any subsequent reads of normal memory.
+    ///
+    ///Intended for reading status of device before accessing memory written by it, e.g. via DMA.
+    ///Barrier is emitted after read: `dmb oshld` on `aarch64`, `fence i, r` on `riscv` and acquire fence on other architectures except `x86`,
+    ///where only compiler is prevented from reordering, as CPU does not reorder such reads.
+    pub fn read_acquire(&self) -> T where T: Copy {
+        let val = self.read();
+        barrier::read();
+        val
+    }
+
+    #[inline]
+    ///Reads single field of data, pointer to which is returned by `project`.
+    ///
+    ///`project` is given pointer to data and must only offset it, e.g. via `core::ptr::addr_of!((*ptr).field)`, without accessing memory.
+    ///Only field is read, hence adjacent registers of `T` are left untouched.
+    ///
+    ///## Panics
+    ///
+    ///If returned pointer is not within data or is not aligned for `U`.
+    pub fn read_field<U: Copy, F: FnOnce(*const T) -> *const U>(&self, project: F) -> U {
+        let field = project(self.ptr.as_ptr());
+        self.check_field(field);
+        unsafe {
+            volatile::read(field)
+        }
+    }
+
+    #[inline]
+    ///Reads data with ordinary read, which compiler is free to optimize, merge or elide.
+    ///
+    ///Intended for memory shared with other processes, where it is synchronized by other means.
+    ///It is not suitable for device registers, which must be accessed via [read](#method.read).
+    pub fn read_plain(&self) -> T where T: Copy {
+        unsafe {
+            ptr::read(self.ptr.as_ptr())
+        }
+    }
+
+    #[inline]
+    ///Reads data without requiring `T: Copy`.
+    ///
+    ///Result is bitwise copy of data, hence it is up to user to ensure that ownership of any resources owned by `T` is not duplicated.
+    pub unsafe fn read_unchecked(&self) -> T {
+        volatile::read(self.ptr.as_ptr())
+    }
+
+    #[inline]
+    ///Reads data without assuming it is valid `T`.
+    ///
+    ///Intended for memory which may be not yet initialized, or be modified concurrently by other process.
+    pub fn read_maybe_uninit(&self) -> mem::MaybeUninit<T> {
         unsafe {
-            ptr::read_volatile(self.ptr)
+            volatile::read(self.ptr.as_ptr() as *const mem::MaybeUninit<T>)
         }
     }
 
+    #[inline]
+    ///Reads data byte by byte, which does not require data to be aligned.
+    ///
+    ///Intended for packed layouts, accessed via [from_ptr](#method.from_ptr) at unaligned address.
+    ///Each byte is read with volatile read, hence it may not be suitable for registers which must be accessed in whole.
+    pub fn read_unaligned(&self) -> T where T: Copy {
+        let mut result = core::mem::MaybeUninit::<T>::uninit();
+        let src = self.ptr.as_ptr() as *const u8;
+        let dst = result.as_mut_ptr() as *mut u8;
+        unsafe {
+            for idx in 0..core::mem::size_of::<T>() {
+                dst.add(idx).write(volatile::read(src.add(idx)));
+            }
+            result.assume_init()
+        }
+    }
+
+    #[inline]
+    ///Reads `U` located `byte_offset` bytes after start of data.
+    ///
+    ///Caller is responsible for `byte_offset + size_of::<U>()` to be within mapping.
+    ///
+    ///## Panics
+    ///
+    ///In debug mode, if resulting pointer is not aligned for `U`.
+    pub unsafe fn read_at<U>(&self, byte_offset: usize) -> U {
+        let ptr = (self.ptr.as_ptr() as *mut u8).add(byte_offset) as *const U;
+        debug_assert!(ptr.is_aligned(), "byte offset {} is misaligned for type with alignment {}", byte_offset, core::mem::align_of::<U>());
+        volatile::read(ptr)
+    }
+}
+
+impl<T, A: access::Writable> MemoryMap<T, A> {
     #[inline]
     ///Writes data
     pub fn write(&mut self, val: T) {
+        #[cfg(feature = "mock")]
+        if let Some(mock) = self.mock.as_ref() {
+            mock.write(val);
+            return;
+        }
+
+        unsafe {
+            volatile::write(self.ptr.as_ptr(), val)
+        }
+    }
+
+    #[inline]
+    ///Writes data, ordering it after any preceding writes to normal memory.
+    ///
+    ///Intended for notifying device about memory prepared for it, e.g. writing doorbell after DMA descriptors.
+    ///Barrier is emitted before write: `dmb oshst` on `aarch64`, `fence w, o` on `riscv` and release fence on other architectures except `x86`,
+    ///where only compiler is prevented from reordering, as CPU does not reorder such writes.
+    pub fn write_release(&mut self, val: T) {
+        barrier::write();
+        self.write(val);
+    }
+
+    #[inline]
+    ///Writes single field of data, pointer to which is returned by `project`.
+    ///
+    ///Same as [read_field](#method.read_field), `project` must only offset pointer to data, without accessing memory.
+    ///
+    ///## Panics
+    ///
+    ///If returned pointer is not within data or is not aligned for `U`.
+    pub fn write_field<U, F: FnOnce(*mut T) -> *mut U>(&mut self, project: F, val: U) {
+        let field = project(self.ptr.as_ptr());
+        self.check_field(field);
+        unsafe {
+            volatile::write(field, val)
+        }
+    }
+
+    #[inline]
+    ///Writes data with ordinary write, which compiler is free to optimize, merge or elide.
+    ///
+    ///Intended for memory shared with other processes, where it is synchronized by other means.
+    ///It is not suitable for device registers, which must be accessed via [write](#method.write).
+    pub fn write_plain(&mut self, val: T) {
         unsafe {
-            ptr::write_volatile(self.ptr, val)
+            ptr::write(self.ptr.as_ptr(), val)
+        }
+    }
+
+    ///Writes `byte` over whole memory starting from data till the end of mapping, using volatile writes.
+    ///
+    ///Memory preceding data within its first page is left untouched.
+    pub fn fill(&mut self, byte: u8) {
+        let len = self.data_len();
+        let dst = self.ptr.as_ptr() as *mut u8;
+        for idx in 0..len {
+            unsafe {
+                volatile::write(dst.add(idx), byte);
+            }
         }
     }
 
+    #[inline]
+    ///Zeroes memory, same as `fill(0)`.
+    pub fn zero(&mut self) {
+        self.fill(0)
+    }
+
+    #[inline]
+    ///Writes data byte by byte, which does not require data to be aligned.
+    ///
+    ///Each byte is written with volatile write, hence it may not be suitable for registers which must be accessed in whole.
+    pub fn write_unaligned(&mut self, val: T) {
+        let val = core::mem::ManuallyDrop::new(val);
+        let src = &*val as *const T as *const u8;
+        let dst = self.ptr.as_ptr() as *mut u8;
+        unsafe {
+            for idx in 0..core::mem::size_of::<T>() {
+                volatile::write(dst.add(idx), *src.add(idx));
+            }
+        }
+    }
+
+    #[inline]
+    ///Writes `U` located `byte_offset` bytes after start of data.
+    ///
+    ///Caller is responsible for `byte_offset + size_of::<U>()` to be within mapping.
+    ///
+    ///## Panics
+    ///
+    ///In debug mode, if resulting pointer is not aligned for `U`.
+    pub unsafe fn write_at<U>(&mut self, byte_offset: usize, val: U) {
+        let ptr = (self.ptr.as_ptr() as *mut u8).add(byte_offset) as *mut U;
+        debug_assert!(ptr.is_aligned(), "byte offset {} is misaligned for type with alignment {}", byte_offset, core::mem::align_of::<U>());
+        volatile::write(ptr, val)
+    }
+}
+
+impl<T, A: access::Readable + access::Writable> MemoryMap<T, A> {
     #[inline]
     ///Gives callback to accept value to return modified value to write.
-    pub fn read_and_write<F: FnOnce(T) -> T>(&mut self, cb: F) {
+    pub fn read_and_write<F: FnOnce(T) -> T>(&mut self, cb: F) where T: Copy {
         let new = cb(self.read());
         self.write(new);
     }
 
     #[inline]
-    #[allow(clippy::needless_lifetimes)]
-    ///Access raw pointer
+    ///Gives callback to accept value to return modified value to write, or error to abort.
     ///
-    ///Note that, ownership is not transferred
-    pub fn as_ref<'a>(&'a mut self) -> RawPtr<'a, T> {
-        RawPtr {
-            ptr: self.ptr,
-            _lifetime: marker::PhantomData
+    ///On error nothing is written and error is returned as it is.
+    pub fn try_modify<E, F: FnOnce(T) -> Result<T, E>>(&mut self, cb: F) -> Result<(), E> where T: Copy {
+        let new = cb(self.read())?;
+        self.write(new);
+        Ok(())
+    }
+
+    #[inline]
+    ///Writes `val`, returning previous value.
+    ///
+    ///It is volatile read followed by volatile write, which is not atomic with respect to device or other CPUs.
+    pub fn replace(&mut self, val: T) -> T where T: Copy {
+        let old = self.read();
+        self.write(val);
+        old
+    }
+
+    #[inline]
+    ///Gives callback to accept value to return modified value to write along with result, which is returned.
+    ///
+    ///Same as [read_and_write](#method.read_and_write), it is not atomic with respect to device or other CPUs.
+    pub fn update<R, F: FnOnce(T) -> (T, R)>(&mut self, cb: F) -> R where T: Copy {
+        let (new, result) = cb(self.read());
+        self.write(new);
+        result
+    }
+}
+
+impl<T: Copy, A: access::Readable> MemoryMap<T, A> {
+    #[inline]
+    ///Reads data until `pred` returns `true`, returning last read value.
+    ///
+    ///Data is read at most `max_spins + 1` times, issuing `spin_loop` hint between reads.
+    ///Returns `TimedOut` error if `pred` is not satisfied by any of them.
+    pub fn wait_for<F: FnMut(T) -> bool>(&self, pred: F, max_spins: usize) -> Result<T, Error> {
+        self.wait_for_with(pred, max_spins, |_| core::hint::spin_loop())
+    }
+
+    ///Reads data until `pred` returns `true`, returning last read value.
+    ///
+    ///Same as [wait_for](#method.wait_for), but calls `backoff` with number of attempt, starting from zero, between reads,
+    ///allowing to sleep while waiting for slow device.
+    pub fn wait_for_with<F: FnMut(T) -> bool, B: FnMut(usize)>(&self, mut pred: F, max_spins: usize, mut backoff: B) -> Result<T, Error> {
+        let mut attempt = 0;
+        loop {
+            let val = self.read();
+            if pred(val) {
+                return Ok(val);
+            }
+            if attempt == max_spins {
+                return Err(Error::new(ErrorKind::TimedOut, 0));
+            }
+
+            backoff(attempt);
+            attempt += 1;
+        }
+    }
+}
+
+impl<T: Atomic, A: access::Readable> MemoryMap<T, A> {
+    #[inline]
+    ///Reads data atomically with `order`.
+    ///
+    ///## Panics
+    ///
+    ///If `order` is `Release` or `AcqRel`.
+    ///Or if data is misaligned for atomic access, e.g. `u64` on 32-bit `x86`, where it is aligned to 4 bytes only.
+    pub fn load_atomic(&self, order: Ordering) -> T {
+        let val = unsafe {
+            T::load(self.ptr.as_ptr(), order)
+        };
+        #[cfg(feature = "trace")]
+        trace::trace(trace::Op::Read, self.ptr.as_ptr(), &val);
+        val
+    }
+}
+
+impl<T: Atomic, A: access::Writable> MemoryMap<T, A> {
+    #[inline]
+    ///Writes data atomically with `order`.
+    ///
+    ///## Panics
+    ///
+    ///If `order` is `Acquire` or `AcqRel`.
+    ///Or if data is misaligned for atomic access, e.g. `u64` on 32-bit `x86`, where it is aligned to 4 bytes only.
+    pub fn store_atomic(&mut self, val: T, order: Ordering) {
+        #[cfg(feature = "trace")]
+        trace::trace(trace::Op::Write, self.ptr.as_ptr(), &val);
+        unsafe {
+            T::store(self.ptr.as_ptr(), val, order)
+        }
+    }
+}
+
+impl<T: Atomic, A: access::Readable + access::Writable> MemoryMap<T, A> {
+    #[inline]
+    ///Stores `new` if current value is equal to `current`, atomically.
+    ///
+    ///Returns previous value, which is `Ok` if it was equal to `current`.
+    ///
+    ///## Panics
+    ///
+    ///If `failure` is `Release` or `AcqRel`.
+    ///Or if data is misaligned for atomic access, e.g. `u64` on 32-bit `x86`, where it is aligned to 4 bytes only.
+    pub fn compare_exchange(&mut self, current: T, new: T, success: Ordering, failure: Ordering) -> Result<T, T> {
+        let result = unsafe {
+            T::compare_exchange(self.ptr.as_ptr(), current, new, success, failure)
+        };
+        #[cfg(feature = "trace")]
+        {
+            let (Ok(prev) | Err(prev)) = result;
+            trace::trace(trace::Op::Read, self.ptr.as_ptr(), &prev);
+            if result.is_ok() {
+                trace::trace(trace::Op::Write, self.ptr.as_ptr(), &new);
+            }
         }
+        result
     }
 
-    #[allow(unused)]
     #[inline]
-    ///Opens memory map.
+    ///Adds `val` atomically with `order`, wrapping around on overflow, and returns previous value.
     ///
-    ///## Arguments
+    ///Same as other atomic read-modify-write operations, it is only meaningful for normal memory,
+    ///shared with other CPUs or processes (e.g. `MAP_SHARED` anonymous memory or `memfd`), as devices generally do not support them.
     ///
-    ///- `offset` - Offset within memory to start.
-    ///- `fd` - File description. -1 for anonymous.
-    ///- `prot` - Memory protection. Specifies operations to be expected. At the very least must be `PROT_READ | PROT_WRITE`
-    ///- `flags` - Specifies whether changes to the mapping are visible across forks. Must be `MAP_ANON` for anonymous.
-    pub unsafe fn open_file_raw(offset: libc::off_t, fd: libc::c_int, prot: libc::c_int, flags: libc::c_int) -> Option<Self> {
-        #[cfg(unix)]
+    ///## Panics
+    ///
+    ///If data is misaligned for atomic access, same as any other atomic operation.
+    pub fn fetch_add(&mut self, val: T, order: Ordering) -> T {
+        self.fetch(atomic::FetchOp::Add, val, order)
+    }
+
+    #[inline]
+    ///Subtracts `val` atomically with `order`, wrapping around on overflow, and returns previous value.
+    pub fn fetch_sub(&mut self, val: T, order: Ordering) -> T {
+        self.fetch(atomic::FetchOp::Sub, val, order)
+    }
+
+    #[inline]
+    ///Performs bitwise and with `val` atomically with `order`, returning previous value.
+    pub fn fetch_and(&mut self, val: T, order: Ordering) -> T {
+        self.fetch(atomic::FetchOp::And, val, order)
+    }
+
+    #[inline]
+    ///Performs bitwise or with `val` atomically with `order`, returning previous value.
+    pub fn fetch_or(&mut self, val: T, order: Ordering) -> T {
+        self.fetch(atomic::FetchOp::Or, val, order)
+    }
+
+    #[inline]
+    ///Performs bitwise xor with `val` atomically with `order`, returning previous value.
+    pub fn fetch_xor(&mut self, val: T, order: Ordering) -> T {
+        self.fetch(atomic::FetchOp::Xor, val, order)
+    }
+
+    #[inline]
+    ///Stores `val` atomically with `order`, returning previous value.
+    pub fn swap(&mut self, val: T, order: Ordering) -> T {
+        self.fetch(atomic::FetchOp::Swap, val, order)
+    }
+
+    #[inline(always)]
+    fn fetch(&mut self, op: atomic::FetchOp, val: T, order: Ordering) -> T {
+        let (prev, _new) = unsafe {
+            T::fetch(self.ptr.as_ptr(), op, val, order)
+        };
+        #[cfg(feature = "trace")]
         {
-            use core::mem;
+            trace::trace(trace::Op::Read, self.ptr.as_ptr(), &prev);
+            trace::trace(trace::Op::Write, self.ptr.as_ptr(), &_new);
+        }
+        prev
+    }
+}
+
+impl<T: Bits, A: access::Readable> MemoryMap<T, A> {
+    #[inline]
+    ///Reads data, returning only bits set in `mask`.
+    pub fn read_bits(&self, mask: T) -> T {
+        self.read() & mask
+    }
 
-            let page_size = libc::sysconf(libc::_SC_PAGESIZE) as libc::off_t;
-            let offset_mask = (page_size - 1);
-            let page_mask = !0u32 as libc::off_t ^ offset_mask;
+    #[inline]
+    ///Returns whether all bits of `mask` are set.
+    pub fn is_set(&self, mask: T) -> bool {
+        self.read_bits(mask) == mask
+    }
 
-            let ptr = libc::mmap(ptr::null_mut(), mem::size_of::<T>(), prot, flags, fd, offset & page_mask);
+    #[inline]
+    ///Waits until all bits of `mask` are set, returning last read value.
+    ///
+    ///Same as [wait_for](#method.wait_for), it returns `TimedOut` error if bits are not set after `max_spins`.
+    pub fn wait_bits_set(&self, mask: T, max_spins: usize) -> Result<T, Error> {
+        self.wait_for(|val| val & mask == mask, max_spins)
+    }
 
-            if ptr == libc::MAP_FAILED {
-                return None;
-            }
+    #[inline]
+    ///Waits until all bits of `mask` are cleared, returning last read value.
+    ///
+    ///Same as [wait_for](#method.wait_for), it returns `TimedOut` error if bits are not cleared after `max_spins`.
+    pub fn wait_bits_clear(&self, mask: T, max_spins: usize) -> Result<T, Error> {
+        self.wait_for(|val| val & mask == T::ZERO, max_spins)
+    }
+}
+
+impl<T: Bits, A: access::Readable + access::Writable> MemoryMap<T, A> {
+    #[inline]
+    ///Sets bits of `mask`, leaving the rest unchanged.
+    pub fn set_bits(&mut self, mask: T) {
+        self.read_and_write(|val| val | mask)
+    }
+
+    #[inline]
+    ///Clears bits of `mask`, leaving the rest unchanged.
+    pub fn clear_bits(&mut self, mask: T) {
+        self.read_and_write(|val| val & !mask)
+    }
+
+    #[inline]
+    ///Toggles bits of `mask`, leaving the rest unchanged.
+    pub fn toggle_bits(&mut self, mask: T) {
+        self.read_and_write(|val| val ^ mask)
+    }
+
+    #[inline]
+    ///Replaces bits of `mask` with bits of `value`, leaving the rest unchanged.
+    ///
+    ///Bits of `value` outside of `mask` are ignored.
+    pub fn modify(&mut self, mask: T, value: T) {
+        self.read_and_write(|val| (val & !mask) | (value & mask))
+    }
+}
 
-            Some(Self {
-                ptr: unsafe {
-                    (ptr as *mut u8).add(offset as usize & offset_mask as usize) as *mut _
-                }
-            })
+impl<T, A: access::Access> MemoryMap<T, A> {
+    #[inline]
+    ///Creates memory map over already accessible memory at `ptr`.
+    ///
+    ///Intended for bare metal targets, where peripherals are accessed by physical address directly.
+    ///Nothing is unmapped on drop.
+    ///
+    ///It is up to user to ensure `ptr` is non-null and valid for access `A` for the lifetime of memory map.
+    pub const unsafe fn from_ptr(ptr: *mut T) -> Self {
+        Self {
+            ptr: ptr::NonNull::new_unchecked(ptr),
+            base: ptr::null_mut(),
+            map_len: core::mem::size_of::<T>(),
+            #[cfg(feature = "mmap")]
+            offset: 0,
+            #[cfg(feature = "mmap")]
+            prot: Protection::of::<A>(),
+            #[cfg(feature = "mmap")]
+            flags: 0,
+            #[cfg(feature = "mmap")]
+            page_size: 0,
+            #[cfg(all(feature = "mmap", unix))]
+            fd: -1,
+            #[cfg(all(feature = "mmap", windows))]
+            handle: ptr::null_mut(),
+            #[cfg(feature = "mock")]
+            mock: None,
+            _access: marker::PhantomData,
         }
+    }
 
-        #[cfg(not(unix))]
-        None
+    #[inline]
+    ///Creates memory map over already accessible memory at `addr`.
+    ///
+    ///Same as [from_ptr](#method.from_ptr), but usable with register addresses from datasheet, including in `static` definitions.
+    pub const unsafe fn from_addr(addr: usize) -> Self {
+        Self::from_ptr(addr as *mut T)
     }
 
-    ///Creates anonymous memory mapping
-    pub fn anonymous() -> Option<Self> {
-        #[cfg(unix)]
+    #[inline]
+    ///Creates memory map over ordinary memory of `data`.
+    ///
+    ///Intended for testing drivers on host without `std`, by substituting register with `static` variable.
+    ///Nothing is unmapped on drop.
+    pub fn from_static(data: &'static mut T) -> Self {
         unsafe {
-            Self::open_file_raw(0, -1, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_ANON | libc::MAP_SHARED)
+            Self::from_ptr(data)
         }
+    }
+
+    #[inline]
+    ///Returns number of bytes mapped.
+    ///
+    ///It includes offset of data within first page, hence it is at least `size_of::<T>()`.
+    pub const fn len(&self) -> usize {
+        self.map_len
+    }
+
+    #[inline]
+    ///Returns whether nothing is mapped.
+    pub const fn is_empty(&self) -> bool {
+        self.map_len == 0
+    }
+
+    #[inline]
+    ///Returns pointer to data.
+    ///
+    ///It points to requested offset, not to base of mapping.
+    pub const fn as_ptr(&self) -> *const T {
+        self.ptr.as_ptr()
+    }
+
+    #[inline]
+    ///Returns mutable pointer to data.
+    ///
+    ///It points to requested offset, not to base of mapping.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    #[inline]
+    ///Access read only raw pointer
+    pub fn as_const_ref(&self) -> RawPtrConst<'_, T, A> {
+        RawPtrConst::new(self.ptr.as_ptr())
+    }
+
+    #[inline]
+    #[allow(clippy::needless_lifetimes)]
+    ///Access raw pointer
+    ///
+    ///Note that, ownership is not transferred
+    pub fn as_ref<'a>(&'a mut self) -> RawPtr<'a, T, A> {
+        RawPtr::new(self.as_mut_ptr())
+    }
+
+    #[inline]
+    ///Returns pointer to field of data, located `byte_offset` bytes after start of data.
+    ///
+    ///Pointer borrows memory map, hence its `read` and `write` access only field, leaving the rest of data untouched.
+    ///It is up to user to ensure that `U` at `byte_offset` is within data and matches its layout.
+    ///
+    ///## Panics
+    ///
+    ///In debug mode, if resulting pointer is not aligned for `U`.
+    pub unsafe fn field<U>(&mut self, byte_offset: usize) -> RawPtr<'_, U, A> {
+        let ptr = (self.ptr.as_ptr() as *mut u8).add(byte_offset) as *mut U;
+        debug_assert!(ptr.is_aligned(), "byte offset {} is misaligned for type with alignment {}", byte_offset, mem::align_of::<U>());
+        RawPtr::new(ptr)
+    }
 
-        #[cfg(not(unix))]
-        None
+    #[inline]
+    ///Returns pointer to field of data, pointer to which is returned by `project`.
+    ///
+    ///Same as [read_field](#method.read_field), `project` must only offset pointer to data, without accessing memory.
+    ///See [project](macro.project.html) macro for convenient usage.
+    ///
+    ///## Panics
+    ///
+    ///If returned pointer is not within data or is not aligned for `U`.
+    pub fn project<U, F: FnOnce(*mut T) -> *mut U>(&mut self, project: F) -> RawPtr<'_, U, A> {
+        let field = project(self.ptr.as_ptr());
+        self.check_field(field);
+        RawPtr::new(field)
+    }
+
+    #[inline]
+    ///Splits data into `H` at start and `P` at `offset` bytes after start, returning pointer to each.
+    ///
+    ///Memory map retains ownership of mapping, while pointers borrow it, hence views are independent of each other,
+    ///but cannot outlive memory map. It is up to user to ensure `H` and `P` match layout of `T`.
+    ///
+    ///## Panics
+    ///
+    ///If `H` does not fit before `offset`, or `P` does not fit within `T` after `offset`.
+    ///In debug mode, if any of pointers is not aligned.
+    pub unsafe fn split_at<H, P>(&mut self, offset: usize) -> (RawPtr<'_, H, A>, RawPtr<'_, P, A>) {
+        assert!(mem::size_of::<H>() <= offset, "header of {} bytes does not fit before offset {}", mem::size_of::<H>(), offset);
+        assert!(offset.checked_add(mem::size_of::<P>()).map_or(false, |end| end <= mem::size_of::<T>()), "offset {} with payload of {} bytes is out of bounds for data of {} bytes", offset, mem::size_of::<P>(), mem::size_of::<T>());
+
+        let head = self.ptr.as_ptr() as *mut H;
+        let tail = (self.ptr.as_ptr() as *mut u8).add(offset) as *mut P;
+        debug_assert!(head.is_aligned(), "data is misaligned for header with alignment {}", mem::align_of::<H>());
+        debug_assert!(tail.is_aligned(), "offset {} is misaligned for payload with alignment {}", offset, mem::align_of::<P>());
+        (RawPtr::new(head), RawPtr::new(tail))
     }
 
-    #[allow(unused)]
-    ///Creates memory mapping on `/dev/mem` which accesses physical memory
+    #[inline]
+    ///Splits data into `H` at start and slice of `P` from `offset` bytes after start till the end of data, returning view of each.
+    ///
+    ///Same as [split_at](#method.split_at), but for payload of variable length, e.g. `[u8]` after header.
+    ///Slice has as many elements as fit within `T` after `offset`.
+    ///
+    ///## Panics
+    ///
+    ///If `H` does not fit before `offset`, `offset` is out of bounds of `T` or `P` is zero sized.
+    ///In debug mode, if any of pointers is not aligned.
+    pub unsafe fn split_at_slice<H, P>(&mut self, offset: usize) -> (RawPtr<'_, H, A>, RawSlice<'_, P, A>) {
+        assert!(mem::size_of::<H>() <= offset, "header of {} bytes does not fit before offset {}", mem::size_of::<H>(), offset);
+        assert!(offset <= mem::size_of::<T>(), "offset {} is out of bounds for data of {} bytes", offset, mem::size_of::<T>());
+        assert!(mem::size_of::<P>() != 0, "payload cannot be zero sized");
+
+        let head = self.ptr.as_ptr() as *mut H;
+        let tail = (self.ptr.as_ptr() as *mut u8).add(offset) as *mut P;
+        debug_assert!(head.is_aligned(), "data is misaligned for header with alignment {}", mem::align_of::<H>());
+        debug_assert!(tail.is_aligned(), "offset {} is misaligned for payload with alignment {}", offset, mem::align_of::<P>());
+        (RawPtr::new(head), RawSlice::new(tail, (mem::size_of::<T>() - offset) / mem::size_of::<P>()))
+    }
+
+    #[inline]
+    ///Consumes memory map, keeping it mapped for the rest of process lifetime.
+    ///
+    ///This is irreversible: memory is never unmapped.
+    ///Any file descriptor or handle retained by memory map is closed, as mapping stays valid without it.
+    pub fn leak(self) -> RawPtr<'static, T, A> {
+        RawPtr::new(self.forget())
+    }
+
+    #[inline]
+    ///Consumes memory map, returning pointer to data without unmapping it.
     ///
-    ///## Arguments
+    ///Use [from_raw](#method.from_raw) to re-create memory map and unmap it on drop.
+    ///It is only possible for memory map created by this crate for exactly `T`, hence mapping of other length
+    ///(e.g. created by `open_bytes`, `remap` or with huge pages) or memory map without mapping (e.g. created by `from_ptr`)
+    ///must use [into_raw_parts](#method.into_raw_parts) instead.
     ///
-    ///- `offset` - Offset within memory to start.
+    ///Any file descriptor or handle retained by memory map is closed, as mapping stays valid without it.
     ///
-    ///Returns `None` on error, further details can be examined by checking last IO error.
-    pub unsafe fn dev_mem(offset: libc::off_t) -> Option<Self> {
-        #[cfg(unix)]
+    ///## Panics
+    ///
+    ///If memory map cannot be re-created by `from_raw`, as described above.
+    pub fn into_raw(self) -> *mut T {
+        #[cfg(all(feature = "mmap", any(unix, windows)))]
         {
-            const DEV_MEM: [u8; 9] = *b"/dev/mem\0";
-            let fd = libc::open(DEV_MEM.as_ptr() as _, libc::O_RDWR | libc::O_SYNC);
-            if fd == -1 {
-                return None;
+            let mapped = !self.base.is_null() || mem::size_of::<T>() == 0;
+            assert!(mapped && self.map_len == self.raw_len(), "memory map of {} bytes cannot be re-created by from_raw, use into_raw_parts", self.map_len);
+        }
+        self.forget()
+    }
+
+    #[inline]
+    ///Consumes memory map, returning pointer to data and length of mapping without unmapping it.
+    ///
+    ///Use [from_raw_parts](#method.from_raw_parts) to re-create memory map and unmap it on drop.
+    ///Length is zero for memory map without mapping (e.g. created by `from_ptr`), as there is nothing to unmap.
+    ///
+    ///Any file descriptor or handle retained by memory map is closed, as mapping stays valid without it.
+    pub fn into_raw_parts(self) -> (*mut T, usize) {
+        let len = match self.base.is_null() {
+            true => 0,
+            false => self.map_len,
+        };
+        (self.forget(), len)
+    }
+
+    #[inline(always)]
+    fn forget(self) -> *mut T {
+        let ptr = self.ptr.as_ptr();
+
+        #[cfg(all(feature = "mmap", unix))]
+        if self.fd != -1 {
+            unsafe {
+                libc::close(self.fd);
             }
+        }
+
+        #[cfg(all(feature = "mmap", windows))]
+        if !self.handle.is_null() {
+            unsafe {
+                win32::CloseHandle(self.handle);
+            }
+        }
+
+        core::mem::forget(self);
+        ptr
+    }
+
+    #[inline]
+    ///Changes access of memory map.
+    ///
+    ///It is up to user to ensure that underlying mapping permits access `B`.
+    pub unsafe fn assume_access<B: access::Access>(self) -> MemoryMap<T, B> {
+        self.reinterpret()
+    }
 
-            let result = Self::open_file_raw(offset, fd, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED);
-            libc::close(fd);
-            result
+    #[inline]
+    ///Converts to memory map of possibly uninitialized `T`.
+    ///
+    ///Any constructor can be used with `MaybeUninit<T>` directly, in order to map memory that is yet to be initialized.
+    pub fn into_uninit(self) -> MemoryMap<mem::MaybeUninit<T>, A> {
+        self.reinterpret()
+    }
+
+    #[inline]
+    fn check_field<U>(&self, field: *const U) {
+        let offset = field.addr().wrapping_sub(self.ptr.as_ptr().addr());
+        assert!(offset.checked_add(mem::size_of::<U>()).map_or(false, |end| end <= mem::size_of::<T>()), "field at offset {} is out of bounds for data of {} bytes", offset as isize, mem::size_of::<T>());
+        assert!(field.is_aligned(), "field at offset {} is misaligned for type with alignment {}", offset, mem::align_of::<U>());
+    }
+
+    #[inline]
+    ///Converts to memory map of `U`, keeping pointer and mapping as they are.
+    ///
+    ///It is up to user to ensure that `U` fits within mapping, is aligned and that data is valid `U`.
+    pub unsafe fn cast<U>(self) -> MemoryMap<U, A> {
+        self.reinterpret()
+    }
+
+    ///Converts to memory map of `U`, checking that it fits within mapping and is aligned.
+    ///
+    ///On failure memory map is returned back together with `TooSmall` or `Misaligned` error.
+    ///
+    ///It is up to user to ensure that data is valid `U`.
+    pub unsafe fn try_cast<U>(self) -> Result<MemoryMap<U, A>, (Self, Error)> {
+        if mem::size_of::<U>() > self.data_len() {
+            Err((self, Error::new(ErrorKind::TooSmall, 0)))
+        } else if !self.ptr.cast::<U>().is_aligned() {
+            Err((self, Error::new(ErrorKind::Misaligned, 0)))
+        } else {
+            Ok(self.reinterpret())
+        }
+    }
+
+    #[inline]
+    ///Returns number of bytes from data till the end of mapping.
+    fn data_len(&self) -> usize {
+        match self.base.is_null() {
+            true => mem::size_of::<T>(),
+            false => self.base as usize + self.map_len - self.ptr.as_ptr() as usize,
         }
+    }
+
+    //Moves ownership of mapping to memory map of `U` with access `B`, keeping pointer as it is.
+    fn reinterpret<U, B>(self) -> MemoryMap<U, B> {
+        let result = MemoryMap {
+            ptr: self.ptr.cast::<U>(),
+            base: self.base,
+            map_len: self.map_len,
+            #[cfg(feature = "mmap")]
+            offset: self.offset,
+            #[cfg(feature = "mmap")]
+            prot: self.prot,
+            #[cfg(feature = "mmap")]
+            flags: self.flags,
+            #[cfg(feature = "mmap")]
+            page_size: self.page_size,
+            #[cfg(all(feature = "mmap", unix))]
+            fd: self.fd,
+            #[cfg(all(feature = "mmap", windows))]
+            handle: self.handle,
+            #[cfg(feature = "mock")]
+            mock: unsafe {
+                ptr::read(&self.mock)
+            },
+            _access: marker::PhantomData,
+        };
+        core::mem::forget(self);
+        result
+    }
+}
+
+impl<T, A: access::Access> MemoryMap<mem::MaybeUninit<T>, A> {
+    #[inline]
+    ///Converts to memory map of `T`, assuming data is initialized.
+    ///
+    ///It is up to user to ensure that data is valid `T`.
+    pub unsafe fn assume_init(self) -> MemoryMap<T, A> {
+        self.reinterpret()
+    }
+}
 
-        #[cfg(not(unix))]
-        None
+impl<T, A: access::Writable> MemoryMap<mem::MaybeUninit<T>, A> {
+    #[inline]
+    ///Initializes data with `val`, converting to memory map of `T`.
+    pub fn write_init(mut self, val: T) -> MemoryMap<T, A> {
+        self.write(mem::MaybeUninit::new(val));
+        self.reinterpret()
     }
 }
 
-impl<T> Drop for MemoryMap<T> {
+impl<T, A> Drop for MemoryMap<T, A> {
     #[inline]
     fn drop(&mut self) {
-        if self.ptr.is_null() {
-            return;
+        //Descriptor is retained even without mapping, e.g. by zero sized memfd
+        #[cfg(all(feature = "mmap", unix))]
+        unsafe {
+            if self.fd != -1 {
+                libc::close(self.fd);
+            }
+            if !self.base.is_null() {
+                let result = libc::munmap(self.base, self.map_len);
+                debug_assert!(result == 0, "failed to unmap memory at {:p} of {} bytes", self.base, self.map_len);
+            }
         }
 
-        #[cfg(unix)]
-        {
-            use core::mem;
-
-            let page_size = unsafe {
-                libc::sysconf(libc::_SC_PAGESIZE) as u32
-            };
-            let offset_mask = page_size - 1;
-            let page_mask: u32 = !0u32 ^ offset_mask;
-            let base_addr = (self.ptr as usize) & page_mask as usize;
-            unsafe {
-                libc::munmap(mem::transmute(base_addr), mem::size_of::<T>());
+        #[cfg(all(feature = "mmap", windows))]
+        unsafe {
+            if !self.handle.is_null() {
+                win32::CloseHandle(self.handle);
+            }
+            if !self.base.is_null() {
+                let result = win32::UnmapViewOfFile(self.base);
+                debug_assert!(result != 0, "failed to unmap memory at {:p}", self.base);
             }
         }
     }
 }
 
-impl<T> fmt::Pointer for MemoryMap<T> {
+impl<T, A> fmt::Pointer for MemoryMap<T, A> {
     #[inline(always)]
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Pointer::fmt(&self.ptr, fmt)
     }
 }
 
-impl<T> fmt::Debug for MemoryMap<T> {
+impl<T, A> fmt::Debug for MemoryMap<T, A> {
     #[inline(always)]
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(&self.ptr, fmt)
     }
 }
 
-unsafe impl<T> Send for MemoryMap<T> {
+//Diagnostic output, including protection and flags mapping was created with
+impl<T, A: access::Access> fmt::Display for MemoryMap<T, A> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fmt = fmt.debug_struct("MemoryMap");
+        fmt.field("ptr", &self.ptr).field("base", &self.base).field("len", &self.map_len);
+        #[cfg(feature = "mmap")]
+        fmt.field("prot", &format_args!("{}", self.prot)).field("flags", &format_args!("{:#x}", self.flags));
+        #[cfg(not(feature = "mmap"))]
+        fmt.field("access", &format_args!("{}", A::NAME));
+        fmt.finish()
+    }
 }
 
-unsafe impl<T> Sync for MemoryMap<T> {
+//Memory map behaves as pointer to `T`, hence it is as thread safe as `T` itself
+unsafe impl<T: Send, A> Send for MemoryMap<T, A> {
+}
+
+unsafe impl<T: Sync, A> Sync for MemoryMap<T, A> {
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::{ErrorKind, MemoryMap};
+
+    #[cfg(all(feature = "std", feature = "mmap"))]
+    ///Temporary file, removed on drop
+    pub(crate) struct TempFile {
+        pub(crate) path: std::path::PathBuf,
+        pub(crate) file: std::fs::File,
+    }
+
+    #[cfg(all(feature = "std", feature = "mmap"))]
+    impl TempFile {
+        ///Creates file of `len` zero bytes, with `name` unique within crate tests.
+        pub(crate) fn new(name: &str, len: u64) -> Self {
+            let path = std::env::temp_dir().join(std::format!("dev-mmio-{}-{name}", std::process::id()));
+            let file = std::fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).expect("to create file");
+            file.set_len(len).expect("to resize file");
+            Self {
+                path,
+                file,
+            }
+        }
+    }
+
+    #[cfg(all(feature = "std", feature = "mmap"))]
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    #[repr(C, align(8))]
+    struct Backing([u8; 16]);
+
+    #[test]
+    fn should_try_cast() {
+        let mut backing = Backing([0; 16]);
+        let map = unsafe {
+            MemoryMap::<[u8; 4]>::from_ptr(backing.0.as_mut_ptr() as *mut [u8; 4])
+        };
+
+        let (map, error) = unsafe {
+            map.try_cast::<u64>().unwrap_err()
+        };
+        assert_eq!(error.kind(), ErrorKind::TooSmall);
+
+        let mut map = unsafe {
+            map.try_cast::<u32>().expect("to fit")
+        };
+        map.write(0x1234_5678);
+        assert_eq!(backing.0[..4], 0x1234_5678u32.to_ne_bytes());
+    }
+
+    #[test]
+    fn should_return_back_misaligned_map() {
+        let mut backing = Backing([0; 16]);
+        let mut map = unsafe {
+            MemoryMap::<[u8; 4]>::from_ptr(backing.0.as_mut_ptr().add(1) as *mut [u8; 4])
+        };
+        map.write([1, 2, 3, 4]);
+
+        let (map, error) = unsafe {
+            map.try_cast::<u32>().unwrap_err()
+        };
+        assert_eq!(error.kind(), ErrorKind::Misaligned);
+        assert_eq!(map.read(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn should_fill_and_zero_only_data() {
+        let mut backing = Backing([0x55; 16]);
+        let mut map = unsafe {
+            MemoryMap::<u32>::from_ptr(backing.0.as_mut_ptr().add(4) as *mut u32)
+        };
+        map.fill(0xab);
+        assert_eq!(map.read(), 0xabab_abab);
+        map.zero();
+        assert_eq!(map.read(), 0);
+        assert_eq!(backing.0, [0x55, 0x55, 0x55, 0x55, 0, 0, 0, 0, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55, 0x55]);
+    }
+
+    #[cfg(all(feature = "mmap", feature = "trace"))]
+    #[test]
+    fn should_fill_with_byte_wide_stores() {
+        use crate::trace::tests::capture;
+        use crate::trace::Op;
+
+        let mut map = MemoryMap::<u64>::try_anonymous().expect("to map");
+        let ptr = map.as_ptr() as *const u8;
+        let accesses = capture(ptr, 8, || map.fill(0xab));
+        assert_eq!(accesses.len(), 8);
+        assert!(accesses.iter().enumerate().all(|(idx, access)| access.op == Op::Write && access.offset == idx && access.value::<u8>() == 0xab));
+
+        let accesses = capture(ptr, 8, || map.zero());
+        assert_eq!(accesses.len(), 8);
+        assert!(accesses.iter().all(|access| access.len() == 1 && access.value::<u8>() == 0));
+        assert_eq!(map.read(), 0);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn should_change_only_masked_bits() {
+        let mut reg = MemoryMap::<u32>::try_anonymous().expect("to map");
+        reg.write(0xf0f0_0000);
+
+        reg.set_bits(0b0101);
+        assert_eq!(reg.read(), 0xf0f0_0005);
+        reg.clear_bits(0xf000_0001);
+        assert_eq!(reg.read(), 0x00f0_0004);
+        reg.toggle_bits(0x00ff_0006);
+        assert_eq!(reg.read(), 0x000f_0002);
+        //Bits of value outside of mask are ignored
+        reg.modify(0xff00, 0xffff_ab00);
+        assert_eq!(reg.read(), 0x000f_ab02);
+
+        assert!(reg.is_set(0x000f_0002));
+        assert!(!reg.is_set(0x001f_0002));
+        assert_eq!(reg.read_bits(0x00ff_00ff), 0x000f_0002);
+    }
+
+    #[cfg(all(feature = "mmap", feature = "trace"))]
+    #[test]
+    fn should_change_bits_with_single_read_and_write() {
+        use crate::trace::tests::capture;
+        use crate::trace::Op;
+
+        let mut reg = MemoryMap::<u16>::try_anonymous().expect("to map");
+        let ptr = reg.as_ptr() as *const u8;
+        let accesses = capture(ptr, 2, || {
+            reg.set_bits(0x1);
+            reg.clear_bits(0x1);
+            reg.toggle_bits(0x1);
+            reg.modify(0xf0, 0x30);
+        });
+        let ops = accesses.iter().map(|access| (access.op == Op::Read, access.value::<u16>())).collect::<std::vec::Vec<_>>();
+        assert_eq!(ops, [(true, 0), (false, 1), (true, 1), (false, 0), (true, 0), (false, 1), (true, 1), (false, 0x31)]);
+
+        let accesses = capture(ptr, 2, || assert!(reg.is_set(0x31)));
+        assert_eq!(accesses.len(), 1);
+        assert!(accesses[0].op == Op::Read);
+    }
+
+    #[cfg(all(feature = "mmap", target_os = "linux"))]
+    #[test]
+    fn should_display_mapping() {
+        use std::string::ToString;
+
+        let mut map = MemoryMap::<u32>::try_anonymous().expect("to map");
+        let (ptr, base) = (map.ptr, map.base);
+        let expected = |prot: &str| std::format!("MemoryMap {{ ptr: {ptr:?}, base: {base:?}, len: 4, prot: {prot}, flags: {:#x} }}", libc::MAP_SHARED | libc::MAP_ANONYMOUS);
+        assert_eq!(map.to_string(), expected("RW-"));
+
+        map.protect(crate::Protection::READ).expect("to protect");
+        assert_eq!(map.to_string(), expected("R--"));
+
+        let map = unsafe {
+            crate::MmapOptions::new().exec(true).map_anonymous::<u32, crate::ReadWrite>().expect("to map")
+        };
+        assert!(map.to_string().contains("prot: RWX"));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn should_split_header_and_payload() {
+        let mut map = MemoryMap::<[u8; 16]>::try_anonymous().expect("to map");
+
+        {
+            let (mut header, mut payload) = unsafe {
+                map.split_at_slice::<u32, u8>(4)
+            };
+            assert_eq!(payload.len(), 12);
+            header.write(payload.len() as u32);
+            for idx in 0..payload.len() {
+                payload.write_at(idx, idx as u8);
+            }
+            assert_eq!(payload.set(12, 0), Err(0));
+            assert_eq!(payload.get(11), Some(11));
+            assert_eq!(payload.get(12), None);
+        }
+
+        let data = map.read();
+        assert_eq!(u32::from_ne_bytes([data[0], data[1], data[2], data[3]]), 12);
+        assert_eq!(data[4..], [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_reject_header_past_offset() {
+        let mut backing = Backing([0; 16]);
+        let mut map = unsafe {
+            MemoryMap::<[u8; 16]>::from_ptr(backing.0.as_mut_ptr() as *mut [u8; 16])
+        };
+        unsafe {
+            map.split_at_slice::<u64, u8>(4);
+        }
+    }
 }
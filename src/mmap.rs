@@ -0,0 +1,1571 @@
+//! Memory mapping via OS facilities
+
+use core::{marker, ptr};
+
+use crate::{access, Advice, Error, ErrorKind, HugePageSize, MemoryMap, MmapOptions, Protection, ReadOnly};
+#[cfg(unix)]
+use crate::unix;
+#[cfg(windows)]
+use crate::win32;
+
+impl<T, A: access::Access> MemoryMap<T, A> {
+    #[cfg(not(windows))]
+    #[allow(unused)]
+    #[inline]
+    #[deprecated(note = "Use try_open_file_raw, which reports cause of failure")]
+    ///Opens memory map, returning `None` on error.
+    ///
+    ///Kept for compatibility, same as [try_open_file_raw](#method.try_open_file_raw).
+    pub unsafe fn open_file_raw(offset: libc::off_t, fd: libc::c_int, prot: libc::c_int, flags: libc::c_int) -> Option<Self> {
+        Self::try_open_file_raw(offset, fd, prot, flags).ok()
+    }
+
+    #[cfg(not(windows))]
+    #[allow(unused)]
+    #[inline]
+    ///Opens memory map.
+    ///
+    ///## Arguments
+    ///
+    ///- `offset` - Offset within memory to start.
+    ///- `fd` - File description. -1 for anonymous.
+    ///- `prot` - Memory protection. Specifies operations to be expected. At the very least must be `PROT_READ | PROT_WRITE`
+    ///- `flags` - Specifies whether changes to the mapping are visible across forks. Must be `MAP_ANON` for anonymous.
+    ///
+    ///`offset` is not required to be page aligned: mapping starts at page containing `offset`
+    ///and its length includes offset within this page, so that whole data is mapped.
+    ///But it must be aligned for `T`, otherwise `Misaligned` error is returned.
+    ///
+    ///It is up to user to ensure `prot` permits access `A`.
+    pub unsafe fn try_open_file_raw(offset: libc::off_t, fd: libc::c_int, prot: libc::c_int, flags: libc::c_int) -> Result<Self, Error> {
+        #[cfg(unix)]
+        {
+            Self::open_file_raw64(unix::offset(offset)?, fd, prot, flags)
+        }
+
+        #[cfg(not(unix))]
+        Err(Error::unsupported())
+    }
+
+    #[cfg(not(windows))]
+    #[allow(unused)]
+    #[inline]
+    ///Opens memory map at 64-bit `offset`.
+    ///
+    ///Same as [try_open_file_raw](#method.try_open_file_raw), but allows offsets above 4 GiB where `off_t` is 32-bit (e.g. physical addresses on 32-bit ARM).
+    ///
+    ///Offset which cannot be represented by platform results in `MapFailed` error with `EOVERFLOW`.
+    pub unsafe fn open_file_raw64(offset: u64, fd: libc::c_int, prot: libc::c_int, flags: libc::c_int) -> Result<Self, Error> {
+        #[cfg(unix)]
+        {
+            unix::map(offset, core::mem::size_of::<T>(), fd, prot, flags).and_then(Self::from_mapping)
+        }
+
+        #[cfg(not(unix))]
+        Err(Error::unsupported())
+    }
+
+    #[cfg(unix)]
+    ///Opens memory map of regular file, checking that it fits data.
+    ///
+    ///Same as [try_open_file_raw](#method.try_open_file_raw), but returns `TooSmall` error if file is shorter than `offset + size_of::<T>()`,
+    ///instead of mapping past end of file, access to which raises `SIGBUS`.
+    ///
+    ///Not suitable for device files such as `/dev/mem`, which report zero size.
+    pub unsafe fn open_file_checked(offset: libc::off_t, fd: libc::c_int, prot: libc::c_int, flags: libc::c_int) -> Result<Self, Error> {
+        let offset = unix::offset(offset)?;
+        let mut stat = core::mem::MaybeUninit::<libc::stat>::uninit();
+        if libc::fstat(fd, stat.as_mut_ptr()) == -1 {
+            return Err(Error::last(ErrorKind::OpenFailed));
+        }
+        match offset.checked_add(core::mem::size_of::<T>() as u64) {
+            Some(end) if end <= stat.assume_init().st_size as u64 => (),
+            _ => return Err(Error::new(ErrorKind::TooSmall, 0)),
+        }
+
+        Self::open_file_raw64(offset, fd, prot, flags)
+    }
+
+    #[cfg(unix)]
+    ///Opens memory map at 64-bit `offset`, which is not required to be aligned for `T`.
+    ///
+    ///Same as [open_file_raw64](#method.open_file_raw64), but intended for devices with registers packed at odd addresses.
+    ///Data of such memory map must be accessed only via [read_unaligned](#method.read_unaligned)
+    ///and [write_unaligned](#method.write_unaligned) unless its alignment is known, as other methods require aligned pointer.
+    pub unsafe fn open_file_raw64_unaligned(offset: u64, fd: libc::c_int, prot: libc::c_int, flags: libc::c_int) -> Result<Self, Error> {
+        unix::map(offset, core::mem::size_of::<T>(), fd, prot, flags).map(Self::from_mapping_unaligned)
+    }
+
+    #[cfg(windows)]
+    ///Opens memory map.
+    ///
+    ///## Arguments
+    ///
+    ///- `offset` - Offset within file to start.
+    ///- `handle` - File handle. `INVALID_HANDLE_VALUE` for memory backed by paging file.
+    ///- `protect` - Page protection of mapping object. At the very least must be `PAGE_READWRITE`
+    ///- `access` - Access to the view. At the very least must be `FILE_MAP_READ | FILE_MAP_WRITE`
+    ///
+    ///`offset` is not required to be aligned: view starts at allocation granularity boundary preceding `offset`
+    ///and its length includes offset within it, so that whole data is mapped.
+    ///But it must be aligned for `T`, otherwise `Misaligned` error is returned.
+    ///
+    ///It is up to user to ensure `protect` and `access` permit access `A`.
+    pub unsafe fn try_open_file_raw(offset: u64, handle: *mut core::ffi::c_void, protect: u32, access: u32) -> Result<Self, Error> {
+        win32::map(offset, core::mem::size_of::<T>(), handle, protect, access).and_then(Self::from_mapping)
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    ///Takes ownership of mapping, unmapping it if data is misaligned for `T`.
+    pub(crate) fn from_mapping(map: unix::Mapping) -> Result<Self, Error> {
+        Self::from_mapping_unaligned(map).check_align()
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    ///Takes ownership of mapping as it is.
+    fn from_mapping_unaligned(map: unix::Mapping) -> Self {
+        Self {
+            //Successful mapping is never at null
+            ptr: unsafe {
+                ptr::NonNull::new_unchecked(map.ptr as *mut T)
+            },
+            base: map.base,
+            map_len: map.len,
+            offset: map.offset,
+            prot: Protection::from_raw(map.prot),
+            flags: map.flags as u32,
+            page_size: map.page,
+            fd: -1,
+            #[cfg(feature = "mock")]
+            mock: None,
+            _access: marker::PhantomData,
+        }
+    }
+
+    #[cfg(windows)]
+    #[inline]
+    ///Takes ownership of mapping, unmapping it if data is misaligned for `T`.
+    pub(crate) fn from_mapping(map: win32::Mapping) -> Result<Self, Error> {
+        Self {
+            //Successful mapping is never at null
+            ptr: unsafe {
+                ptr::NonNull::new_unchecked(map.ptr as *mut T)
+            },
+            base: map.base,
+            map_len: map.len,
+            offset: map.offset,
+            prot: Protection::from_raw(map.protect),
+            flags: map.access,
+            page_size: map.page,
+            handle: map.handle,
+            #[cfg(feature = "mock")]
+            mock: None,
+            _access: marker::PhantomData,
+        }.check_align()
+    }
+
+    #[inline]
+    fn check_align(self) -> Result<Self, Error> {
+        match self.ptr.is_aligned() {
+            true => Ok(self),
+            false => Err(Error::new(ErrorKind::Misaligned, 0)),
+        }
+    }
+
+    #[inline]
+    ///Returns offset within file or memory, which was requested when mapping.
+    ///
+    ///It is offset of data, not of page aligned base of mapping.
+    ///Zero if memory map is not created at offset, e.g. via [from_ptr](#method.from_ptr) or [from_raw](#method.from_raw).
+    pub const fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    ///Returns file descriptor retained by memory map, if any.
+    ///
+    ///Descriptor remains owned by memory map and is closed on drop.
+    pub fn raw_fd(&self) -> Option<libc::c_int> {
+        match self.fd {
+            -1 => None,
+            fd => Some(fd),
+        }
+    }
+
+    #[cfg(unix)]
+    ///Creates shared memory mapping of file at `path`
+    ///
+    ///File is closed once mapping is created.
+    ///
+    ///## Arguments
+    ///
+    ///- `path` - Path to file. Being `CStr` it is always NUL terminated.
+    ///- `offset` - Offset within file to start.
+    ///- `open_flags` - Flags to open file with. Must grant access required by `prot`.
+    ///- `prot` - Memory protection. It is up to user to ensure `prot` permits access `A`.
+    ///
+    ///Failure to open file is reported as `OpenFailed`, distinct from `MapFailed`.
+    pub unsafe fn open_path_raw(path: &core::ffi::CStr, offset: libc::off_t, open_flags: libc::c_int, prot: libc::c_int) -> Result<Self, Error> {
+        Self::open_path_raw64(path, unix::offset(offset)?, open_flags, prot)
+    }
+
+    #[cfg(unix)]
+    unsafe fn open_path_raw64(path: &core::ffi::CStr, offset: u64, open_flags: libc::c_int, prot: libc::c_int) -> Result<Self, Error> {
+        unix::open_with(path, open_flags, |fd| Self::open_file_raw64(offset, fd, prot, libc::MAP_SHARED))
+    }
+
+    #[inline]
+    ///Flushes changes to backing file, waiting for write to complete on unix.
+    ///
+    ///Only meaningful for shared file mappings.
+    ///On windows it is `FlushViewOfFile`, which only starts writing dirty pages back, same as [flush_async](#method.flush_async).
+    ///To wait until they reach disk, call `FlushFileBuffers` (e.g. `File::sync_data`) on file handle afterwards.
+    pub fn flush(&self) -> Result<(), Error> {
+        self.mapped()?;
+
+        #[cfg(unix)]
+        {
+            self.msync(libc::MS_SYNC)
+        }
+
+        #[cfg(windows)]
+        {
+            self.flush_async()
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        Err(Error::unsupported())
+    }
+
+    ///Flushes changes within `len` bytes, located `byte_offset` bytes after start of data, waiting for write to complete on unix.
+    ///
+    ///Range is extended to boundaries of pages backing mapping, which are huge pages if mapping is created with them.
+    ///Same as [flush](#method.flush), it is only meaningful for shared file mappings and does not wait on windows.
+    ///
+    ///Returns `TooSmall` error if range is not within mapping.
+    pub fn flush_range(&self, byte_offset: usize, len: usize) -> Result<(), Error> {
+        self.mapped()?;
+
+        match byte_offset.checked_add(len) {
+            Some(end) if end <= self.data_len() => (),
+            _ => return Err(Error::new(ErrorKind::TooSmall, 0)),
+        }
+        let start = unsafe {
+            (self.ptr.as_ptr() as *mut u8).add(byte_offset)
+        };
+
+        #[cfg(unix)]
+        {
+            let in_page = start as usize & (self.page_size - 1);
+            match unsafe { libc::msync(start.sub(in_page) as *mut _, in_page + len, libc::MS_SYNC) } {
+                -1 => Err(Error::last(ErrorKind::FlushFailed)),
+                _ => Ok(()),
+            }
+        }
+
+        #[cfg(windows)]
+        match unsafe { win32::FlushViewOfFile(start as *const _, len) } {
+            0 => Err(Error::last(ErrorKind::FlushFailed)),
+            _ => Ok(()),
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = start;
+            Err(Error::unsupported())
+        }
+    }
+
+    #[inline]
+    ///Schedules flush of changes to backing file, without waiting for it to complete.
+    ///
+    ///Only meaningful for shared file mappings.
+    pub fn flush_async(&self) -> Result<(), Error> {
+        self.mapped()?;
+
+        #[cfg(unix)]
+        {
+            self.msync(libc::MS_ASYNC)
+        }
+
+        #[cfg(windows)]
+        match unsafe { win32::FlushViewOfFile(self.base, self.map_len) } {
+            0 => Err(Error::last(ErrorKind::FlushFailed)),
+            _ => Ok(()),
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        Err(Error::unsupported())
+    }
+
+    #[inline]
+    ///Invalidates cached copies of mapping, so that changes made to backing file by others become visible.
+    ///
+    ///Returns `Unsupported` error on non-unix platforms, where views of the same file are always coherent.
+    pub fn invalidate(&self) -> Result<(), Error> {
+        self.mapped()?;
+
+        #[cfg(unix)]
+        {
+            self.msync(libc::MS_INVALIDATE)
+        }
+
+        #[cfg(not(unix))]
+        Err(Error::unsupported())
+    }
+
+    #[inline]
+    ///Changes memory protection of whole mapping.
+    ///
+    ///`prot` must include at least accesses that are going to be performed, otherwise they will fault.
+    ///Access `A` is not changed, hence it is up to user to ensure `prot` still permits it.
+    ///For example, writing to `ReadWrite` memory map after protecting it with `Protection::READ` faults.
+    ///Use [assume_access](#method.assume_access) to reflect new protection in type.
+    ///
+    ///On windows write implies read, and protection cannot exceed one mapping was created with.
+    pub fn protect(&mut self, prot: Protection) -> Result<(), Error> {
+        self.mapped()?;
+
+        #[cfg(unix)]
+        match unsafe { libc::mprotect(self.base, self.map_len, prot.as_raw()) } {
+            -1 => Err(Error::last(ErrorKind::ProtectFailed)),
+            _ => {
+                self.prot = prot;
+                Ok(())
+            },
+        }
+
+        #[cfg(windows)]
+        {
+            let mut old = 0;
+            match unsafe { win32::VirtualProtect(self.base, self.map_len, prot.as_raw(), &mut old) } {
+                0 => Err(Error::last(ErrorKind::ProtectFailed)),
+                _ => {
+                    self.prot = prot;
+                    Ok(())
+                },
+            }
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = prot;
+            Err(Error::unsupported())
+        }
+    }
+
+    #[inline]
+    ///Makes instruction cache coherent with data written to mapping, so that it can be executed.
+    ///
+    ///Required after writing machine code on architectures, such as `aarch64` and `arm`, whose instruction cache is not coherent with data cache.
+    ///It is no-op on `x86`, while on windows it is `FlushInstructionCache`.
+    ///
+    ///Returns `Unsupported` error on unix architectures, for which it is not implemented.
+    pub fn flush_icache(&self) -> Result<(), Error> {
+        self.mapped()?;
+
+        #[cfg(unix)]
+        unsafe {
+            unix::clear_cache(self.base as *const u8, self.map_len)
+        }
+
+        #[cfg(windows)]
+        match unsafe { win32::FlushInstructionCache(win32::GetCurrentProcess(), self.base, self.map_len) } {
+            0 => Err(Error::last(ErrorKind::ProtectFailed)),
+            _ => Ok(()),
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        Err(Error::unsupported())
+    }
+
+    #[inline]
+    ///Protects mapping as `Protection::READ | Protection::EXEC` and flushes instruction cache, so that code written to it can be executed.
+    ///
+    ///Mapping must be created with execute permission (e.g. [MmapOptions::exec](struct.MmapOptions.html#method.exec)),
+    ///otherwise protection might be refused.
+    ///
+    ///It is recommended to never have memory writable and executable at the same time (W^X):
+    ///map memory without execute permission or [protect](#method.protect) it as writable only while code is written,
+    ///then make it executable. Once executable, writing to mapping faults despite access `A`,
+    ///hence map should be converted via [assume_access](#method.assume_access) to `ReadOnly`.
+    ///
+    ///Use [flush_icache](#method.flush_icache) instead to keep writable protection.
+    ///
+    ///## Example
+    ///
+    ///```
+    ///#[cfg(all(target_arch = "x86_64", unix))]
+    ///{
+    ///    use dev_mmio::{MmapOptions, ReadWrite};
+    ///
+    ///    let mut map = unsafe {
+    ///        MmapOptions::new().exec(true).private().map_anonymous::<[u8; 6], ReadWrite>()
+    ///    }.expect("to map");
+    ///    //mov eax, 42; ret
+    ///    map.write([0xb8, 42, 0, 0, 0, 0xc3]);
+    ///    map.make_executable().expect("to make executable");
+    ///
+    ///    let func: extern "C" fn() -> u32 = unsafe {
+    ///        core::mem::transmute(map.as_ptr())
+    ///    };
+    ///    assert_eq!(func(), 42);
+    ///}
+    ///```
+    pub fn make_executable(&mut self) -> Result<(), Error> {
+        self.protect(Protection::READ | Protection::EXEC)?;
+        self.flush_icache()
+    }
+
+    #[inline]
+    ///Locks pages of mapping in RAM, preventing them from being swapped out.
+    ///
+    ///Fails if limit of locked memory (e.g. `RLIMIT_MEMLOCK` on linux) would be exceeded.
+    ///On linux pages can also be locked at creation by passing `MAP_LOCKED` to [try_open_file_raw](#method.try_open_file_raw).
+    ///
+    ///Note that locks do not stack: single `unlock` releases pages regardless of number of `lock` calls.
+    ///On macOS amount of locked memory is limited by system wide wired memory limit (`vm.user_wire_limit`).
+    ///
+    ///Cause of failure can be told by [raw_os_error](struct.Error.html#method.raw_os_error):
+    ///on unix `EPERM` means lack of privilege (e.g. `CAP_IPC_LOCK`), while `ENOMEM` means limit is exceeded.
+    pub fn lock(&self) -> Result<(), Error> {
+        self.mapped()?;
+
+        #[cfg(unix)]
+        match unsafe { libc::mlock(self.base, self.map_len) } {
+            -1 => Err(Error::last(ErrorKind::LockFailed)),
+            _ => Ok(()),
+        }
+
+        #[cfg(windows)]
+        match unsafe { win32::VirtualLock(self.base, self.map_len) } {
+            0 => Err(Error::last(ErrorKind::LockFailed)),
+            _ => Ok(()),
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        Err(Error::unsupported())
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[inline]
+    ///Locks pages of mapping in RAM as they are faulted in, using `mlock2` with `MLOCK_ONFAULT`.
+    ///
+    ///Unlike [lock](#method.lock) it does not populate whole mapping upfront.
+    pub fn lock_on_fault(&self) -> Result<(), Error> {
+        self.mapped()?;
+
+        match unsafe { libc::mlock2(self.base, self.map_len, libc::MLOCK_ONFAULT) } {
+            -1 => Err(Error::last(ErrorKind::LockFailed)),
+            _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    ///Unlocks pages of mapping, previously locked by [lock](#method.lock).
+    pub fn unlock(&self) -> Result<(), Error> {
+        self.mapped()?;
+
+        #[cfg(unix)]
+        match unsafe { libc::munlock(self.base, self.map_len) } {
+            -1 => Err(Error::last(ErrorKind::LockFailed)),
+            _ => Ok(()),
+        }
+
+        #[cfg(windows)]
+        match unsafe { win32::VirtualUnlock(self.base, self.map_len) } {
+            0 => Err(Error::last(ErrorKind::LockFailed)),
+            _ => Ok(()),
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        Err(Error::unsupported())
+    }
+
+    #[inline]
+    ///Advises kernel about expected usage of whole mapping.
+    ///
+    ///Returns `Unsupported` error on non-unix platforms and for advice not available on current platform.
+    ///
+    ///Advice not applicable to kind of mapping (e.g. `HugePage` on file mapping) fails with `AdviseFailed` and `EINVAL`.
+    pub fn advise(&self, advice: Advice) -> Result<(), Error> {
+        self.mapped()?;
+
+        #[cfg(unix)]
+        match advice.as_raw() {
+            Some(advice) => match unsafe { libc::madvise(self.base, self.map_len, advice) } {
+                -1 => Err(Error::last(ErrorKind::AdviseFailed)),
+                _ => Ok(()),
+            },
+            None => Err(Error::unsupported()),
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = advice;
+            Err(Error::unsupported())
+        }
+    }
+
+    ///Changes length of mapping to `new_len` bytes, preserving its content.
+    ///
+    ///Length includes offset of data within first page, same as [len](#method.len), and must be enough to fit `T`, otherwise `TooSmall` error is returned.
+    ///If `may_move` is `true`, kernel is allowed to move mapping when it cannot be grown in place,
+    ///otherwise it fails with `ResizeFailed` and `ENOMEM`.
+    ///Moving mapping invalidates any pointers to it, including `RawPtr` obtained previously.
+    ///
+    ///Shared mapping can only grow within its backing object, which for [try_anonymous](#method.try_anonymous) is of initial length,
+    ///hence accessing beyond it raises `SIGBUS`. Use private mapping to grow anonymous memory.
+    ///
+    ///Returns `Unsupported` error on platforms other than linux and for memory map created by [from_ptr](#method.from_ptr).
+    pub fn remap(&mut self, new_len: usize, may_move: bool) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        {
+            self.mapped()?;
+
+            let in_page = self.ptr.as_ptr() as usize - self.base as usize;
+            if new_len < in_page + core::mem::size_of::<T>() {
+                return Err(Error::new(ErrorKind::TooSmall, 0));
+            }
+
+            let flags = match may_move {
+                true => libc::MREMAP_MAYMOVE,
+                false => 0,
+            };
+            let base = unsafe {
+                libc::mremap(self.base, self.map_len, new_len, flags)
+            };
+            if base == libc::MAP_FAILED {
+                return Err(Error::last(ErrorKind::ResizeFailed));
+            }
+
+            self.ptr = unsafe {
+                ptr::NonNull::new_unchecked((base as *mut u8).add(in_page) as *mut T)
+            };
+            self.base = base;
+            self.map_len = new_len;
+            Ok(())
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (new_len, may_move);
+            Err(Error::unsupported())
+        }
+    }
+
+    ///Creates independent memory map of the same shared memory, which is unmapped on its own.
+    ///
+    ///On linux it is `mremap` with zero old size, which duplicates mapping, hence writes through one are visible through other.
+    ///File descriptor retained by memory map is duplicated.
+    ///
+    ///Private mapping cannot be duplicated this way, hence it fails with `MapFailed` and `EINVAL`.
+    ///Returns `Unsupported` error on other platforms and for memory map created by [from_ptr](#method.from_ptr).
+    pub fn try_clone(&self) -> Result<Self, Error> {
+        #[cfg(target_os = "linux")]
+        {
+            self.mapped()?;
+
+            unsafe {
+                let base = libc::mremap(self.base, 0, self.map_len, libc::MREMAP_MAYMOVE);
+                if base == libc::MAP_FAILED {
+                    return Err(Error::last(ErrorKind::MapFailed));
+                }
+
+                let fd = match self.fd {
+                    -1 => -1,
+                    fd => match libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) {
+                        -1 => {
+                            let error = Error::last(ErrorKind::OpenFailed);
+                            libc::munmap(base, self.map_len);
+                            return Err(error);
+                        },
+                        fd => fd,
+                    },
+                };
+
+                let in_page = self.ptr.as_ptr() as usize - self.base as usize;
+                let mut result = Self::from_mapping(unix::Mapping {
+                    base,
+                    len: self.map_len,
+                    ptr: (base as *mut u8).add(in_page),
+                    offset: self.offset,
+                    prot: self.prot.as_raw(),
+                    flags: self.flags as libc::c_int,
+                    page: self.page_size,
+                })?;
+                result.fd = fd;
+                Ok(result)
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        Err(Error::unsupported())
+    }
+
+    ///Unmaps memory, reporting failure which is ignored on drop.
+    ///
+    ///Any file descriptor or handle retained by memory map is closed regardless of result.
+    ///Memory map created by [from_ptr](#method.from_ptr) has nothing to unmap, hence it always succeeds.
+    pub fn unmap(self) -> Result<(), Error> {
+        let this = core::mem::ManuallyDrop::new(self);
+        #[cfg(feature = "mock")]
+        drop(unsafe {
+            ptr::read(&this.mock)
+        });
+        #[cfg(unix)]
+        unsafe {
+            let result = match this.base.is_null() || libc::munmap(this.base, this.map_len) == 0 {
+                true => Ok(()),
+                false => Err(Error::last(ErrorKind::UnmapFailed)),
+            };
+            if this.fd != -1 {
+                libc::close(this.fd);
+            }
+            result
+        }
+
+        #[cfg(windows)]
+        unsafe {
+            let result = match this.base.is_null() || win32::UnmapViewOfFile(this.base) != 0 {
+                true => Ok(()),
+                false => Err(Error::last(ErrorKind::UnmapFailed)),
+            };
+            if !this.handle.is_null() {
+                win32::CloseHandle(this.handle);
+            }
+            result
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        Err(Error::unsupported())
+    }
+
+    #[inline]
+    //Operations on mapping are unsupported for memory map without one, e.g. created via `from_ptr`
+    fn mapped(&self) -> Result<(), Error> {
+        match self.base.is_null() {
+            true => Err(Error::unsupported()),
+            false => Ok(()),
+        }
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    fn msync(&self, flags: libc::c_int) -> Result<(), Error> {
+        match unsafe { libc::msync(self.base, self.map_len, flags) } {
+            -1 => Err(Error::last(ErrorKind::FlushFailed)),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(any(unix, windows))]
+    #[inline(always)]
+    fn granularity() -> usize {
+        #[cfg(unix)]
+        {
+            unix::granularity()
+        }
+        #[cfg(windows)]
+        {
+            win32::granularity()
+        }
+    }
+
+    #[cfg(any(unix, windows))]
+    #[inline(always)]
+    //Length of mapping that from_raw derives for pointer to data
+    pub(crate) fn raw_len(&self) -> usize {
+        match core::mem::size_of::<T>() {
+            0 => 0,
+            size => (self.ptr.as_ptr() as usize & (Self::granularity() - 1)) + size,
+        }
+    }
+
+    #[cfg(any(unix, windows))]
+    ///Re-creates memory map from pointer returned by [into_raw](#method.into_raw).
+    ///
+    ///Base address and length of mapping are derived from `ptr` the same way as they are computed when mapping `T` with normal pages.
+    ///
+    ///`ptr` must be returned by `into_raw` of memory map with the same `T`, which is mapped by this crate for exactly `T`,
+    ///and must not be used to re-create memory map more than once. Otherwise behavior is undefined.
+    ///In particular memory map created by `from_ptr`, `from_static`, `open_bytes`, with huge pages
+    ///or changed by `remap` must be re-created by [from_raw_parts](#method.from_raw_parts) instead.
+    pub unsafe fn from_raw(ptr: *mut T) -> Self {
+        let in_page = ptr as usize & (Self::granularity() - 1);
+        let len = match core::mem::size_of::<T>() {
+            0 => 0,
+            size => in_page + size,
+        };
+        Self::from_raw_parts(ptr, len)
+    }
+
+    #[cfg(any(unix, windows))]
+    ///Re-creates memory map from parts returned by [into_raw_parts](#method.into_raw_parts).
+    ///
+    ///Base address of mapping is derived from `ptr` the same way as it is computed when mapping, while `len` is its length.
+    ///
+    ///Parts must be returned by `into_raw_parts` of memory map with the same `T` and must not be used to re-create memory map more than once.
+    ///Otherwise behavior is undefined.
+    ///
+    ///Mapping is assumed to be backed by normal pages, hence [flush_range](#method.flush_range) and [prefault](#method.prefault)
+    ///operate on them, even if it was created with huge pages.
+    pub unsafe fn from_raw_parts(ptr: *mut T, len: usize) -> Self {
+        let in_page = ptr as usize & (Self::granularity() - 1);
+        //Zero sized data is not mapped
+        let base = match len {
+            0 => ptr::null_mut(),
+            _ => (ptr as *mut u8).sub(in_page) as *mut _,
+        };
+
+        Self {
+            ptr: ptr::NonNull::new_unchecked(ptr),
+            base,
+            map_len: len,
+            offset: 0,
+            prot: Protection::of::<A>(),
+            flags: 0,
+            page_size: page_size(),
+            #[cfg(unix)]
+            fd: -1,
+            #[cfg(windows)]
+            handle: ptr::null_mut(),
+            #[cfg(feature = "mock")]
+            mock: None,
+            _access: marker::PhantomData,
+        }
+    }
+}
+
+impl<A: access::Access> MemoryMap<u8, A> {
+    #[cfg(unix)]
+    ///Opens memory map of exactly `len` bytes, regardless of size of data.
+    ///
+    ///Same as [try_open_file_raw](#method.try_open_file_raw), but intended for memory whose size is known only at runtime (e.g. framebuffer).
+    ///Data is then accessed via [read_at](#method.read_at) and [write_at](#method.write_at).
+    ///
+    ///[len](#method.len) includes offset within first page, and it is at least one byte even if `len` is zero.
+    pub unsafe fn open_bytes(offset: libc::off_t, fd: libc::c_int, prot: libc::c_int, flags: libc::c_int, len: usize) -> Result<Self, Error> {
+        unix::map(unix::offset(offset)?, len.max(1), fd, prot, flags).and_then(Self::from_mapping)
+    }
+}
+
+impl<T> MemoryMap<T> {
+    #[inline]
+    #[deprecated(note = "Use try_anonymous, which reports cause of failure")]
+    ///Creates anonymous memory mapping, returning `None` on error.
+    ///
+    ///Kept for compatibility, same as [try_anonymous](#method.try_anonymous).
+    pub fn anonymous() -> Option<Self> {
+        Self::try_anonymous().ok()
+    }
+
+    ///Creates anonymous memory mapping
+    pub fn try_anonymous() -> Result<Self, Error> {
+        unsafe {
+            MmapOptions::new().map_anonymous()
+        }
+    }
+
+    ///Creates anonymous memory mapping backed by huge pages of `size`
+    ///
+    ///Length of mapping is rounded up to multiple of `size`, hence it is unmapped, flushed and advised in whole huge pages.
+    ///If there are no huge pages of `size` available, it fails with `NoHugePages` error instead of falling back to normal pages.
+    ///
+    ///Returns `Unsupported` error on platforms other than linux and macOS, where only 2 MiB pages are supported.
+    ///
+    ///Note that [from_raw](#method.from_raw) cannot re-create such memory map, as it assumes normal pages.
+    pub fn anonymous_huge(size: HugePageSize) -> Result<Self, Error> {
+        unsafe {
+            MmapOptions::new().huge_pages(size).map_anonymous()
+        }
+    }
+
+    #[allow(unused)]
+    #[inline]
+    #[deprecated(note = "Use try_dev_mem, which reports cause of failure")]
+    ///Creates memory mapping on `/dev/mem` which accesses physical memory, returning `None` on error.
+    ///
+    ///Kept for compatibility, same as [try_dev_mem](#method.try_dev_mem).
+    pub unsafe fn dev_mem(offset: libc::off_t) -> Option<Self> {
+        Self::try_dev_mem(offset).ok()
+    }
+
+    #[allow(unused)]
+    ///Creates memory mapping on `/dev/mem` which accesses physical memory
+    ///
+    ///Returns `Unsupported` error on macOS and other Apple platforms, which have no `/dev/mem`.
+    ///
+    ///## Arguments
+    ///
+    ///- `offset` - Offset within memory to start. Must be aligned for `T`, otherwise `Misaligned` error is returned.
+    pub unsafe fn try_dev_mem(offset: libc::off_t) -> Result<Self, Error> {
+        #[cfg(all(unix, not(target_vendor = "apple")))]
+        {
+            Self::open_path(c"/dev/mem", offset)
+        }
+
+        #[cfg(not(all(unix, not(target_vendor = "apple"))))]
+        Err(Error::unsupported())
+    }
+
+    #[cfg(unix)]
+    ///Creates memory mapping on `/dev/mem`, which is not required to be aligned for `T`.
+    ///
+    ///Same as [try_dev_mem](#method.try_dev_mem), but data must be accessed only via `read_unaligned` and `write_unaligned`,
+    ///see [open_file_raw64_unaligned](#method.open_file_raw64_unaligned).
+    pub unsafe fn dev_mem_unaligned(offset: libc::off_t) -> Result<Self, Error> {
+        #[cfg(not(target_vendor = "apple"))]
+        {
+            let offset = unix::offset(offset)?;
+            unix::open_with(c"/dev/mem", libc::O_RDWR | libc::O_SYNC | libc::O_CLOEXEC, |fd| Self::open_file_raw64_unaligned(offset, fd, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED))
+        }
+
+        #[cfg(target_vendor = "apple")]
+        {
+            let _ = offset;
+            Err(Error::unsupported())
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    ///Creates memory mapping on `/dev/gpiomem`, which accesses GPIO registers without need for root
+    ///
+    ///`offset` is relative to start of GPIO registers block, rather than physical memory.
+    ///Fails with `NotFound` error if there is no `/dev/gpiomem`, in which case [try_dev_mem](#method.try_dev_mem) can be used instead.
+    ///
+    ///## Example
+    ///
+    ///```no_run
+    ///use dev_mmio::MemoryMap;
+    ///
+    /////BCM2835 GPIO registers
+    ///const GPSET0: usize = 0x1c;
+    ///const GPCLR0: usize = 0x28;
+    ///const PIN: u32 = 17;
+    ///
+    ///let mut gpio = unsafe {
+    ///    MemoryMap::<[u32; 41]>::gpio_mem(0)
+    ///}.expect("to map GPIO registers");
+    ///
+    ///unsafe {
+    ///    gpio.write_at::<u32>(GPSET0, 1 << PIN);
+    ///    gpio.write_at::<u32>(GPCLR0, 1 << PIN);
+    ///}
+    ///```
+    pub unsafe fn gpio_mem(offset: libc::off_t) -> Result<Self, Error> {
+        let result = Self::open_path_raw(c"/dev/gpiomem", offset, libc::O_RDWR | libc::O_CLOEXEC, libc::PROT_READ | libc::PROT_WRITE);
+        result.map_err(|error| match (error.kind(), error.raw_os_error()) {
+            (ErrorKind::OpenFailed, libc::ENOENT) => Error::new(ErrorKind::NotFound, libc::ENOENT),
+            _ => error,
+        })
+    }
+
+    #[cfg(unix)]
+    ///Creates memory mapping on `/dev/mem` at 64-bit physical address `offset`
+    ///
+    ///Unlike [try_dev_mem](#method.try_dev_mem) it can reach physical memory above 4 GiB on 32-bit targets.
+    pub unsafe fn dev_mem64(offset: u64) -> Result<Self, Error> {
+        #[cfg(not(target_vendor = "apple"))]
+        {
+            Self::open_path_raw64(c"/dev/mem", offset, libc::O_RDWR | libc::O_SYNC | libc::O_CLOEXEC, libc::PROT_READ | libc::PROT_WRITE)
+        }
+
+        #[cfg(target_vendor = "apple")]
+        {
+            let _ = offset;
+            Err(Error::unsupported())
+        }
+    }
+
+    #[cfg(unix)]
+    ///Creates shared memory mapping of file at `path`
+    ///
+    ///File is opened with `O_RDWR | O_SYNC | O_CLOEXEC` and closed once mapping is created.
+    ///Suitable for device files such as `/dev/uio0` or `/dev/gpiomem`.
+    ///
+    ///## Arguments
+    ///
+    ///- `path` - Path to file. Being `CStr` it is always NUL terminated.
+    ///- `offset` - Offset within file to start.
+    pub unsafe fn open_path(path: &core::ffi::CStr, offset: libc::off_t) -> Result<Self, Error> {
+        Self::open_path_raw(path, offset, libc::O_RDWR | libc::O_SYNC | libc::O_CLOEXEC, libc::PROT_READ | libc::PROT_WRITE)
+    }
+
+    #[cfg(unix)]
+    ///Creates shared memory mapping of file descriptor, taking ownership of it.
+    ///
+    ///Descriptor is retained until memory map is dropped, making it possible to pass it to other processes (e.g. one created by [memfd](#method.memfd)).
+    ///File must be at least `size_of::<T>()` bytes.
+    ///
+    ///`fd` must be opened for reading and writing.
+    ///
+    ///On error `fd` is left open and its ownership remains with caller.
+    pub unsafe fn from_fd(fd: libc::c_int) -> Result<Self, Error> {
+        let mut stat = core::mem::MaybeUninit::<libc::stat>::uninit();
+        if libc::fstat(fd, stat.as_mut_ptr()) == -1 {
+            return Err(Error::last(ErrorKind::OpenFailed));
+        }
+        if (stat.assume_init().st_size as u64) < core::mem::size_of::<T>() as u64 {
+            return Err(Error::new(ErrorKind::TooSmall, 0));
+        }
+
+        let mut result = Self::try_open_file_raw(0, fd, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED)?;
+        result.fd = fd;
+        Ok(result)
+    }
+
+    #[cfg(all(feature = "std", any(unix, windows)))]
+    ///Creates shared memory mapping of `file`
+    ///
+    ///File must be opened for reading and writing. It can be closed once mapping is created.
+    ///
+    ///## Arguments
+    ///
+    ///- `file` - File to map.
+    ///- `offset` - Offset within file to start.
+    ///
+    ///Returns `TooSmall` error if file is shorter than `offset + size_of::<T>()`.
+    pub unsafe fn from_file(file: &std::fs::File, offset: u64) -> Result<Self, Error> {
+        let len = file.metadata().map_err(|error| Error::new(ErrorKind::OpenFailed, error.raw_os_error().unwrap_or(0)))?.len();
+        match offset.checked_add(core::mem::size_of::<T>() as u64) {
+            Some(end) if end <= len => (),
+            _ => return Err(Error::new(ErrorKind::TooSmall, 0)),
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+
+            Self::open_file_raw64(offset, file.as_raw_fd(), libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED)
+        }
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::io::AsRawHandle;
+
+            Self::try_open_file_raw(offset, file.as_raw_handle(), win32::PAGE_READWRITE, win32::FILE_MAP_READ | win32::FILE_MAP_WRITE)
+        }
+    }
+
+    #[cfg(all(feature = "std", any(unix, windows)))]
+    ///Creates shared memory mapping of file at `path`
+    ///
+    ///File is opened for reading and writing, and closed once mapping is created.
+    ///
+    ///## Arguments
+    ///
+    ///- `path` - Path to file.
+    ///- `offset` - Offset within file to start.
+    pub unsafe fn open<P: AsRef<std::path::Path>>(path: P, offset: u64) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+        Self::from_file(&file, offset).map_err(Into::into)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Creates shared memory mapping backed by anonymous file, created with `memfd_create`.
+    ///
+    ///File is sized to fit `T` and retained by memory map, so that it can be passed to other process via [raw_fd](#method.raw_fd)
+    ///and mapped there using [from_fd](#method.from_fd).
+    ///
+    ///`name` is used only for debugging purposes and may be duplicate.
+    pub fn memfd(name: &core::ffi::CStr) -> Result<Self, Error> {
+        unsafe {
+            let fd = libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC);
+            if fd == -1 {
+                return Err(Error::last(ErrorKind::OpenFailed));
+            }
+
+            if libc::ftruncate(fd, core::mem::size_of::<T>() as libc::off_t) == -1 {
+                let error = Error::last(ErrorKind::ResizeFailed);
+                libc::close(fd);
+                return Err(error);
+            }
+
+            match Self::from_fd(fd) {
+                Ok(result) => Ok(result),
+                Err(error) => {
+                    libc::close(fd);
+                    Err(error)
+                }
+            }
+        }
+    }
+
+    #[cfg(all(unix, not(target_os = "android")))]
+    ///Creates memory mapping of POSIX shared memory object, which can be opened by other processes.
+    ///
+    ///## Arguments
+    ///
+    ///- `name` - Name of object. Must start with `/` and contain no other slashes.
+    ///- `create` - Whether to create new object, failing with `EEXIST` if it already exists. Otherwise opens existing object.
+    ///
+    ///Newly created object is sized to fit `T`, while existing object must be at least `size_of::<T>()` bytes.
+    ///If mapping of newly created object fails, it is unlinked.
+    pub fn shared_named(name: &core::ffi::CStr, create: bool) -> Result<Self, Error> {
+        use core::mem;
+
+        let name_bytes = name.to_bytes();
+        match name_bytes.split_first() {
+            Some((b'/', rest)) if !rest.is_empty() && !rest.contains(&b'/') => (),
+            _ => return Err(Error::new(ErrorKind::OpenFailed, libc::EINVAL)),
+        }
+
+        let size = mem::size_of::<T>();
+        let flags = match create {
+            true => libc::O_CREAT | libc::O_EXCL | libc::O_RDWR,
+            false => libc::O_RDWR,
+        };
+
+        unsafe {
+            #[cfg(any(target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
+            let fd = libc::shm_open(name.as_ptr(), flags, 0o600 as libc::c_uint);
+            #[cfg(not(any(target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos")))]
+            let fd = libc::shm_open(name.as_ptr(), flags, 0o600 as libc::mode_t);
+            if fd == -1 {
+                return Err(Error::last(ErrorKind::OpenFailed));
+            }
+
+            let result = match create {
+                true => match libc::ftruncate(fd, size as libc::off_t) {
+                    -1 => Err(Error::last(ErrorKind::ResizeFailed)),
+                    _ => Ok(()),
+                },
+                false => {
+                    let mut stat = mem::MaybeUninit::<libc::stat>::uninit();
+                    if libc::fstat(fd, stat.as_mut_ptr()) == -1 {
+                        Err(Error::last(ErrorKind::OpenFailed))
+                    } else if (stat.assume_init().st_size as u64) < size as u64 {
+                        Err(Error::new(ErrorKind::TooSmall, 0))
+                    } else {
+                        Ok(())
+                    }
+                },
+            };
+            let result = result.and_then(|_| Self::try_open_file_raw(0, fd, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED));
+            libc::close(fd);
+
+            if create && result.is_err() {
+                libc::shm_unlink(name.as_ptr());
+            }
+
+            result
+        }
+    }
+}
+
+impl<T, A: access::Readable> MemoryMap<T, A> {
+    #[inline]
+    ///Faults in every page of mapping by reading its first byte via volatile read.
+    ///
+    ///It front-loads cost of page faults, so that latency of subsequent accesses is not affected by them,
+    ///though pages can still be evicted unless mapping is [locked](#method.lock).
+    ///To fault in pages at creation, use [MmapOptions::populate](struct.MmapOptions.html#method.populate),
+    ///which on linux is done by kernel via `MAP_POPULATE`.
+    ///
+    ///Does nothing for memory map created by [from_ptr](#method.from_ptr).
+    pub fn prefault(&self) {
+        touch(self);
+    }
+}
+
+impl<T> MemoryMap<T, ReadOnly> {
+    #[allow(unused)]
+    ///Creates read only memory mapping on `/dev/mem` which accesses physical memory
+    ///
+    ///Returns `Unsupported` error on macOS and other Apple platforms, which have no `/dev/mem`.
+    ///
+    ///## Arguments
+    ///
+    ///- `offset` - Offset within memory to start.
+    pub unsafe fn dev_mem_read_only(offset: libc::off_t) -> Result<Self, Error> {
+        #[cfg(all(unix, not(target_vendor = "apple")))]
+        {
+            Self::open_path_raw(c"/dev/mem", offset, libc::O_RDONLY | libc::O_SYNC | libc::O_CLOEXEC, libc::PROT_READ)
+        }
+
+        #[cfg(not(all(unix, not(target_vendor = "apple"))))]
+        Err(Error::unsupported())
+    }
+}
+
+#[cfg(any(unix, windows))]
+///Reads first byte of every page of mapping, faulting it in.
+pub(crate) fn touch<T, A>(map: &MemoryMap<T, A>) {
+    if map.base.is_null() {
+        return;
+    }
+
+    let base = map.base as *const u8;
+    let mut offset = 0;
+    while offset < map.map_len {
+        unsafe {
+            ptr::read_volatile(base.add(offset));
+        }
+        offset += map.page_size;
+    }
+}
+
+#[cfg(any(unix, windows))]
+#[inline]
+///Returns size of memory page.
+///
+///It is queried from system once and cached afterwards.
+///
+///```
+///let size = dev_mmio::page_size();
+///assert!(size.is_power_of_two());
+///assert!(size >= 4096);
+///assert_eq!(dev_mmio::allocation_granularity() % size, 0);
+///```
+pub fn page_size() -> usize {
+    #[cfg(unix)]
+    {
+        unix::granularity()
+    }
+    #[cfg(windows)]
+    {
+        win32::page_size()
+    }
+}
+
+#[cfg(any(unix, windows))]
+#[inline]
+///Returns allocation granularity, which offset of file mapping is rounded down to.
+///
+///It is page size on unix, while on windows it is usually 64 KiB.
+///It is queried from system once and cached afterwards.
+pub fn allocation_granularity() -> usize {
+    #[cfg(unix)]
+    {
+        unix::granularity()
+    }
+    #[cfg(windows)]
+    {
+        win32::granularity()
+    }
+}
+
+#[cfg(all(unix, not(target_os = "android")))]
+///Removes POSIX shared memory object created by [shared_named](struct.MemoryMap.html#method.shared_named)
+///
+///Existing mappings remain valid, but object can no longer be opened by name.
+pub fn unlink_named(name: &core::ffi::CStr) -> Result<(), Error> {
+    match unsafe { libc::shm_unlink(name.as_ptr()) } {
+        -1 => Err(Error::last(ErrorKind::UnlinkFailed)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MemoryMap;
+
+    #[cfg(unix)]
+    #[test]
+    #[allow(deprecated)]
+    fn should_keep_option_constructors() {
+        let mut map = MemoryMap::<u32>::anonymous().expect("to map");
+        map.write(1);
+        assert_eq!(map.read(), 1);
+
+        let map = unsafe {
+            MemoryMap::<u32>::open_file_raw(0, -1, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED)
+        };
+        assert!(map.is_none());
+    }
+
+    #[test]
+    fn should_unmap_map_without_mapping() {
+        let mut data = 0u32;
+        let map = unsafe {
+            MemoryMap::<u32>::from_ptr(&mut data)
+        };
+        map.unmap().expect("to succeed");
+        MemoryMap::<()>::try_anonymous().expect("to map").unmap().expect("to succeed");
+    }
+
+    #[test]
+    fn should_not_support_operations_without_mapping() {
+        use crate::{Advice, ErrorKind, Protection};
+
+        let mut data = 0u32;
+        let mut map = unsafe {
+            MemoryMap::<u32>::from_ptr(&mut data)
+        };
+        assert_eq!(map.flush().unwrap_err().kind(), ErrorKind::Unsupported);
+        assert_eq!(map.flush_range(0, 4).unwrap_err().kind(), ErrorKind::Unsupported);
+        assert_eq!(map.flush_async().unwrap_err().kind(), ErrorKind::Unsupported);
+        assert_eq!(map.invalidate().unwrap_err().kind(), ErrorKind::Unsupported);
+        assert_eq!(map.protect(Protection::READ).unwrap_err().kind(), ErrorKind::Unsupported);
+        assert_eq!(map.flush_icache().unwrap_err().kind(), ErrorKind::Unsupported);
+        assert_eq!(map.lock().unwrap_err().kind(), ErrorKind::Unsupported);
+        #[cfg(target_os = "linux")]
+        assert_eq!(map.lock_on_fault().unwrap_err().kind(), ErrorKind::Unsupported);
+        assert_eq!(map.unlock().unwrap_err().kind(), ErrorKind::Unsupported);
+        assert_eq!(map.advise(Advice::WillNeed).unwrap_err().kind(), ErrorKind::Unsupported);
+        map.prefault();
+        map.write(1);
+        assert_eq!(data, 1);
+    }
+
+    #[test]
+    fn should_advise_anonymous_memory() {
+        use crate::Advice;
+
+        let mut map = unsafe {
+            crate::MmapOptions::new().private().map_anonymous::<u32, crate::ReadWrite>().expect("to map")
+        };
+        map.write(1);
+
+        #[cfg(unix)]
+        {
+            for advice in [Advice::Normal, Advice::Random, Advice::Sequential, Advice::WillNeed] {
+                map.advise(advice).expect("to advise");
+            }
+            assert_eq!(map.read(), 1);
+        }
+
+        //Private anonymous pages are dropped, hence they are zero filled on next access
+        #[cfg(target_os = "linux")]
+        {
+            map.advise(Advice::DontNeed).expect("to advise");
+            assert_eq!(map.read(), 0);
+        }
+
+        #[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+        assert_eq!(map.advise(Advice::HugePage).unwrap_err().kind(), crate::ErrorKind::Unsupported);
+
+        #[cfg(not(unix))]
+        assert_eq!(map.advise(Advice::WillNeed).unwrap_err().kind(), crate::ErrorKind::Unsupported);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn should_remap_to_grow_and_shrink() {
+        let page = crate::page_size();
+        let mut map = unsafe {
+            crate::MmapOptions::new().private().map_anonymous::<u32, crate::ReadWrite>().expect("to map")
+        };
+        map.write(0x1234_5678);
+
+        map.remap(page * 4, true).expect("to grow");
+        assert_eq!(map.len(), page * 4);
+        assert_eq!(map.read(), 0x1234_5678);
+        unsafe {
+            map.write_at::<u32>(page * 4 - 4, 0x8765_4321);
+            assert_eq!(map.read_at::<u32>(page * 4 - 4), 0x8765_4321);
+        }
+
+        map.remap(4, false).expect("to shrink in place");
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.read(), 0x1234_5678);
+        assert_eq!(map.remap(2, true).unwrap_err().kind(), crate::ErrorKind::TooSmall);
+        map.unmap().expect("to unmap shrunk mapping");
+    }
+
+    #[test]
+    fn should_recreate_from_raw() {
+        let mut map = MemoryMap::<u32>::try_anonymous().expect("to map");
+        map.write(0x1234_5678);
+        let map = unsafe {
+            MemoryMap::<u32>::from_raw(map.into_raw())
+        };
+        assert_eq!(map.read(), 0x1234_5678);
+        map.unmap().expect("to unmap re-created mapping");
+
+        let map = MemoryMap::<()>::try_anonymous().expect("to map");
+        let map = unsafe {
+            MemoryMap::<()>::from_raw(map.into_raw())
+        };
+        assert!(map.is_empty());
+    }
+
+    #[repr(C, align(4096))]
+    struct Page([u8; 4096]);
+
+    #[test]
+    #[should_panic(expected = "cannot be re-created by from_raw")]
+    fn should_reject_into_raw_without_mapping() {
+        //Page aligned pointer would otherwise pass as data of mapping
+        let mut page = Page([0; 4096]);
+        let map = unsafe {
+            MemoryMap::<u32>::from_ptr(page.0.as_mut_ptr() as *mut u32)
+        };
+        map.into_raw();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    #[should_panic(expected = "cannot be re-created by from_raw")]
+    fn should_reject_into_raw_of_other_length() {
+        let map = unsafe {
+            MemoryMap::<u8>::open_bytes(0, -1, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_ANON | libc::MAP_SHARED, 8).expect("to map")
+        };
+        map.into_raw();
+    }
+
+    #[test]
+    fn should_recreate_from_raw_parts() {
+        //Private anonymous mapping is the only one supported by Miri
+        let mut map = unsafe {
+            crate::MmapOptions::new().private().map_anonymous::<u32, crate::ReadWrite>().expect("to map")
+        };
+        map.write(0x1234_5678);
+        let (ptr, len) = map.into_raw_parts();
+        assert_eq!(len, 4);
+
+        let mut map = unsafe {
+            MemoryMap::<u32>::from_raw_parts(ptr, len)
+        };
+        assert_eq!(map.as_ptr(), ptr as *const u32);
+        assert_eq!(map.read(), 0x1234_5678);
+        map.write(0x8765_4321);
+        assert_eq!(map.read(), 0x8765_4321);
+        map.unmap().expect("to unmap re-created mapping");
+    }
+
+    #[test]
+    fn should_not_unmap_from_raw_parts_without_mapping() {
+        let mut data = 0u32;
+        let map = unsafe {
+            MemoryMap::<u32>::from_ptr(&mut data)
+        };
+        let (ptr, len) = map.into_raw_parts();
+        assert_eq!(len, 0);
+        let map = unsafe {
+            MemoryMap::<u32>::from_raw_parts(ptr, len)
+        };
+        map.unmap().expect("to have nothing to unmap");
+        assert_eq!(data, 0);
+    }
+
+    #[cfg(all(feature = "std", any(unix, windows)))]
+    #[test]
+    fn should_write_back_file() {
+        use std::io::{Read, Seek};
+
+        let mut file = crate::tests::TempFile::new("write-back", 8);
+        let mut map = unsafe {
+            MemoryMap::<u64>::from_file(&file.file, 0).expect("to map file")
+        };
+        map.write(0x0102_0304_0506_0708);
+        map.flush().expect("to flush");
+        drop(map);
+
+        let mut bytes = [0u8; 8];
+        file.file.rewind().expect("to seek");
+        file.file.read_exact(&mut bytes).expect("to read file");
+        assert_eq!(u64::from_ne_bytes(bytes), 0x0102_0304_0506_0708);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_change_protection() {
+        use crate::Protection;
+
+        let mut map = unsafe {
+            MemoryMap::<u32>::try_open_file_raw(0, -1, libc::PROT_READ, libc::MAP_ANON | libc::MAP_PRIVATE).expect("to map")
+        };
+        map.protect(Protection::READ | Protection::WRITE).expect("to make writable");
+        map.write(0x1234_5678);
+
+        map.protect(Protection::READ).expect("to make read only");
+        assert_eq!(map.prot, Protection::READ);
+        assert_eq!(map.read(), 0x1234_5678);
+
+        //Write to read only memory must fault, which is observed in child process
+        match unsafe { libc::fork() } {
+            -1 => panic!("fork failed"),
+            0 => unsafe {
+                core::ptr::write_volatile(map.as_mut_ptr(), 0);
+                libc::_exit(0)
+            },
+            child => {
+                let mut status = 0;
+                assert_eq!(unsafe { libc::waitpid(child, &mut status, 0) }, child);
+                assert!(libc::WIFSIGNALED(status));
+                assert!([libc::SIGSEGV, libc::SIGBUS].contains(&libc::WTERMSIG(status)));
+            },
+        }
+
+        map.protect(Protection::READ | Protection::WRITE).expect("to make writable again");
+        map.write(0x8765_4321);
+        assert_eq!(map.read(), 0x8765_4321);
+    }
+
+    #[cfg(all(feature = "std", any(unix, windows)))]
+    #[test]
+    fn should_map_from_file() {
+        use std::io::Write;
+
+        let mut file = crate::tests::TempFile::new("from-file", 0);
+        file.file.write_all(&[0, 0, 0, 0, 1, 2, 3, 4]).expect("to write file");
+
+        //Mapping outlives file it is created from
+        let clone = file.file.try_clone().expect("to clone file");
+        let map = unsafe {
+            MemoryMap::<[u8; 4]>::from_file(&clone, 4).expect("to map file")
+        };
+        drop(clone);
+        assert_eq!(map.offset(), 4);
+        assert_eq!(map.read(), [1, 2, 3, 4]);
+
+        for offset in [5, 8, u64::MAX] {
+            let error = unsafe {
+                MemoryMap::<[u8; 4]>::from_file(&file.file, offset).unwrap_err()
+            };
+            assert_eq!(error.kind(), crate::ErrorKind::TooSmall);
+        }
+    }
+
+    #[cfg(all(feature = "std", unix))]
+    #[test]
+    fn should_reject_file_shorter_than_data() {
+        use std::os::unix::io::AsRawFd;
+
+        let file = crate::tests::TempFile::new("checked-too-small", 4);
+        let fd = file.file.as_raw_fd();
+        let prot = libc::PROT_READ | libc::PROT_WRITE;
+
+        let error = unsafe {
+            MemoryMap::<u64>::open_file_checked(0, fd, prot, libc::MAP_SHARED).unwrap_err()
+        };
+        assert_eq!(error.kind(), crate::ErrorKind::TooSmall);
+        let error = unsafe {
+            MemoryMap::<u32>::open_file_checked(4, fd, prot, libc::MAP_SHARED).unwrap_err()
+        };
+        assert_eq!(error.kind(), crate::ErrorKind::TooSmall);
+
+        let mut map = unsafe {
+            MemoryMap::<u32>::open_file_checked(0, fd, prot, libc::MAP_SHARED).expect("to fit file")
+        };
+        map.write(1);
+        assert_eq!(map.read(), 1);
+    }
+
+    #[cfg(all(feature = "std", unix))]
+    #[test]
+    fn should_flush_at_offset_within_page() {
+        use std::os::unix::fs::FileExt;
+
+        let file = crate::tests::TempFile::new("flush-pread", 64);
+        //Data is not at page boundary, yet whole page is synced
+        let mut map = unsafe {
+            MemoryMap::<u64>::from_file(&file.file, 16).expect("to map file")
+        };
+        let mut bytes = [0u8; 8];
+
+        map.write(0x1122_3344_5566_7788);
+        map.flush().expect("to flush");
+        file.file.read_exact_at(&mut bytes, 16).expect("to pread");
+        assert_eq!(u64::from_ne_bytes(bytes), 0x1122_3344_5566_7788);
+
+        map.write(0x8877_6655_4433_2211);
+        map.flush_async().expect("to schedule flush");
+        map.invalidate().expect("to invalidate");
+        file.file.read_exact_at(&mut bytes, 16).expect("to pread");
+        assert_eq!(u64::from_ne_bytes(bytes), 0x8877_6655_4433_2211);
+    }
+
+    #[cfg(all(feature = "std", unix))]
+    #[test]
+    fn should_flush_range_of_single_page() {
+        use std::os::unix::fs::FileExt;
+
+        const LEN: usize = 65536;
+        let file = crate::tests::TempFile::new("flush-range", LEN as u64);
+        let mut map = unsafe {
+            MemoryMap::<[u8; LEN]>::from_file(&file.file, 0).expect("to map file")
+        };
+        //Last page is dirtied and flushed, starting in the middle of it
+        let dirty = LEN - crate::page_size() + 12;
+        unsafe {
+            map.write_at::<u32>(dirty, 0x1234_5678);
+        }
+        map.flush_range(dirty, 4).expect("to flush range");
+
+        let mut bytes = [0u8; 4];
+        file.file.read_exact_at(&mut bytes, dirty as u64).expect("to pread");
+        assert_eq!(u32::from_ne_bytes(bytes), 0x1234_5678);
+
+        assert_eq!(map.flush_range(LEN - 1, 2).unwrap_err().kind(), crate::ErrorKind::TooSmall);
+        assert_eq!(map.flush_range(1, usize::MAX).unwrap_err().kind(), crate::ErrorKind::TooSmall);
+        map.flush_range(0, LEN).expect("to flush whole mapping");
+    }
+
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    #[test]
+    fn should_unmap_file_mapped_at_unaligned_offset() {
+        let file = crate::tests::TempFile::new("unaligned-unmap", 2 * crate::page_size() as u64);
+        let is_mapped = || std::fs::read_to_string("/proc/self/maps").expect("to read maps").contains(&*file.path.to_string_lossy());
+
+        for offset in [4, 12, crate::page_size() as u64 - 8, crate::page_size() as u64 + 4] {
+            for _ in 0..100 {
+                let map = unsafe {
+                    MemoryMap::<u32>::from_file(&file.file, offset).expect("to map file")
+                };
+                assert!((map.as_ptr() as usize) & (crate::page_size() - 1) == offset as usize & (crate::page_size() - 1));
+                assert!(is_mapped());
+                drop(map);
+                assert!(!is_mapped());
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_map_dev_zero_across_page_at_unaligned_offset() {
+        let page = crate::page_size();
+        let fd = unsafe {
+            libc::open(c"/dev/zero".as_ptr(), libc::O_RDWR | libc::O_CLOEXEC)
+        };
+        assert_ne!(fd, -1);
+
+        let mut map = unsafe {
+            MemoryMap::<u64>::open_file_raw64_unaligned(page as u64 - 4, fd, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_PRIVATE)
+        }.expect("to map");
+        unsafe {
+            libc::close(fd);
+        }
+        //Data straddles pages, both of which are mapped from start of first one
+        assert_eq!(map.as_ptr() as usize & (page - 1), page - 4);
+        assert_eq!(map.len(), page + 4);
+        assert_eq!(map.read_unaligned(), 0);
+        map.write_unaligned(u64::MAX);
+        assert_eq!(map.read_unaligned(), u64::MAX);
+
+        let mut vec = [0u8; 2];
+        let base = (map.as_ptr() as usize - (page - 4)) as *mut libc::c_void;
+        assert_eq!(unsafe { libc::mincore(base, map.len(), vec.as_mut_ptr() as *mut _) }, 0);
+        map.unmap().expect("to unmap both pages");
+    }
+
+    #[cfg(target_os = "linux")]
+    fn is_memfd(fd: libc::c_int, name: &str) -> bool {
+        match std::fs::read_link(std::format!("/proc/self/fd/{fd}")) {
+            Ok(path) => path.to_string_lossy().starts_with(&std::format!("/memfd:{name}")),
+            Err(_) => false,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn should_close_fd_of_zero_sized_memfd() {
+        for _ in 0..10 {
+            let map = MemoryMap::<()>::memfd(c"dev-mmio-zst-drop").expect("to create memfd");
+            let fd = map.raw_fd().expect("to retain fd");
+            assert!(is_memfd(fd, "dev-mmio-zst-drop"));
+            drop(map);
+            assert!(!is_memfd(fd, "dev-mmio-zst-drop"));
+
+            let map = MemoryMap::<()>::memfd(c"dev-mmio-zst-unmap").expect("to create memfd");
+            let fd = map.raw_fd().expect("to retain fd");
+            map.unmap().expect("to unmap");
+            assert!(!is_memfd(fd, "dev-mmio-zst-unmap"));
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Mock memory for testing drivers without hardware
+//!
+//!Memory map created by [MemoryMap::mock](../struct.MemoryMap.html#method.mock) is backed by heap memory,
+//!while accompanying [MockHandle](struct.MockHandle.html) allows to intercept and record accesses to it.
+//!
+//!Only `read` and `write` (and methods built on them, such as `read_and_write` or `set_bits`) go through hooks and are recorded.
+//!Split accesses of `u64`, such as `read_split_lo_hi`, do the same per half: hooks and record cover whole value.
+//!Other methods access memory directly.
+//!
+//!Without `std`, driver can be tested over ordinary memory via [MemoryMap::from_static](../struct.MemoryMap.html#method.from_static) instead.
+//!
+//!## Usage
+//!
+//!```
+//!use dev_mmio::MemoryMap;
+//!use dev_mmio::mock::Access;
+//!
+//!const ENABLE: u32 = 1 << 0;
+//!const MODE: u32 = 0b11 << 4;
+//!
+//!//Driver under test
+//!fn enable(ctrl: &mut MemoryMap<u32>, mode: u32) {
+//!    ctrl.modify(MODE, mode << 4);
+//!    ctrl.set_bits(ENABLE);
+//!}
+//!
+//!let (mut ctrl, handle) = MemoryMap::<u32>::mock(0xff00_0000);
+//!enable(&mut ctrl, 2);
+//!
+//!assert!(ctrl.is_set(ENABLE));
+//!//Bits outside of fields are preserved
+//!assert_eq!(handle.value(), 0xff00_0021);
+//!assert_eq!(handle.accesses(), [
+//!    Access::Read(0xff00_0000), Access::Write(0xff00_0020),
+//!    Access::Read(0xff00_0020), Access::Write(0xff00_0021),
+//!    Access::Read(0xff00_0021),
+//!]);
+//!```
+
+use core::{marker, mem, ptr};
+use std::boxed::Box;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::vec::Vec;
+
+use crate::{access, MemoryMap};
+
+//Hook operates on memory in place, so that memory map can change type of data without losing hooks
+type Hook = Box<dyn FnMut(*mut u8) + Send>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Access to mock memory
+pub enum Access<T> {
+    ///Value returned by read
+    Read(T),
+    ///Value written
+    Write(T),
+}
+
+struct State {
+    on_read: Option<Hook>,
+    on_write: Option<Hook>,
+    //Copies of whole memory, which may include uninitialized padding
+    accesses: Vec<Access<Vec<mem::MaybeUninit<u8>>>>,
+}
+
+pub(crate) struct Inner {
+    ptr: *mut u8,
+    //Size of memory
+    len: usize,
+    free: unsafe fn(*mut u8),
+    state: Mutex<State>,
+}
+
+impl Inner {
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, State> {
+        //Hook panicking is not reason to stop recording
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    #[inline]
+    fn bytes(&self) -> Vec<mem::MaybeUninit<u8>> {
+        let mut bytes = Vec::with_capacity(self.len);
+        unsafe {
+            ptr::copy_nonoverlapping(self.ptr as *const mem::MaybeUninit<u8>, bytes.as_mut_ptr(), self.len);
+            bytes.set_len(self.len);
+        }
+        bytes
+    }
+
+    #[inline]
+    pub(crate) fn read<T: Copy>(&self) -> T {
+        self.read_at(0)
+    }
+
+    #[inline]
+    pub(crate) fn write<T>(&self, val: T) {
+        self.write_at(0, val)
+    }
+
+    //Accesses part of memory at `offset`, while hooks and record still cover whole memory
+    pub(crate) fn read_at<T: Copy>(&self, offset: usize) -> T {
+        debug_assert!(offset + mem::size_of::<T>() <= self.len);
+        let mut state = self.state();
+        if let Some(on_read) = state.on_read.as_mut() {
+            on_read(self.ptr);
+        }
+        let bytes = self.bytes();
+        state.accesses.push(Access::Read(bytes));
+        unsafe {
+            ptr::read_volatile(self.ptr.add(offset) as *const T)
+        }
+    }
+
+    pub(crate) fn write_at<T>(&self, offset: usize, val: T) {
+        debug_assert!(offset + mem::size_of::<T>() <= self.len);
+        let mut state = self.state();
+        unsafe {
+            ptr::write_volatile(self.ptr.add(offset) as *mut T, val);
+        }
+        if let Some(on_write) = state.on_write.as_mut() {
+            on_write(self.ptr);
+        }
+        let bytes = self.bytes();
+        state.accesses.push(Access::Write(bytes));
+    }
+}
+
+impl Drop for Inner {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            (self.free)(self.ptr)
+        }
+    }
+}
+
+//Memory is only accessed by hooks under lock or via memory map and handle, which require `T` to be thread safe
+unsafe impl Send for Inner {
+}
+
+unsafe impl Sync for Inner {
+}
+
+unsafe fn free<T>(ptr: *mut u8) {
+    drop(Box::from_raw(ptr as *mut T));
+}
+
+///Handle to mock memory, shared with its memory map
+pub struct MockHandle<T> {
+    inner: Arc<Inner>,
+    _type: marker::PhantomData<T>,
+}
+
+impl<T: Copy + 'static> MockHandle<T> {
+    ///Sets hook to be called on every read with current value, returning value to be read and stored.
+    pub fn on_read<F: FnMut(T) -> T + Send + 'static>(&self, mut cb: F) {
+        self.inner.state().on_read = Some(Box::new(move |ptr| unsafe {
+            let ptr = ptr as *mut T;
+            ptr::write_volatile(ptr, cb(ptr::read_volatile(ptr)));
+        }));
+    }
+
+    ///Sets hook to be called on every write with written value, returning value to be stored.
+    pub fn on_write<F: FnMut(T) -> T + Send + 'static>(&self, mut cb: F) {
+        self.inner.state().on_write = Some(Box::new(move |ptr| unsafe {
+            let ptr = ptr as *mut T;
+            ptr::write_volatile(ptr, cb(ptr::read_volatile(ptr)));
+        }));
+    }
+
+    ///Returns current value of memory, without calling hooks or recording access.
+    pub fn value(&self) -> T {
+        let _state = self.inner.state();
+        unsafe {
+            ptr::read_volatile(self.inner.ptr as *const T)
+        }
+    }
+
+    ///Returns accesses recorded so far, in order.
+    pub fn accesses(&self) -> Vec<Access<T>> {
+        //Copy is of the same size as `T`
+        let read = |bytes: &Vec<mem::MaybeUninit<u8>>| unsafe {
+            ptr::read_unaligned(bytes.as_ptr() as *const T)
+        };
+        self.inner.state().accesses.iter().map(|access| match access {
+            Access::Read(bytes) => Access::Read(read(bytes)),
+            Access::Write(bytes) => Access::Write(read(bytes)),
+        }).collect()
+    }
+
+    ///Forgets accesses recorded so far.
+    pub fn clear(&self) {
+        self.inner.state().accesses.clear();
+    }
+}
+
+impl<T> Clone for MockHandle<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _type: marker::PhantomData,
+        }
+    }
+}
+
+impl<T: Copy + 'static, A: access::Access> MemoryMap<T, A> {
+    ///Creates memory map over heap memory, initialized with `initial`, and handle to it.
+    ///
+    ///Memory is freed once both memory map and all handles are dropped.
+    pub fn mock(initial: T) -> (Self, MockHandle<T>) {
+        let inner = Arc::new(Inner {
+            ptr: Box::into_raw(Box::new(initial)) as *mut u8,
+            len: mem::size_of::<T>(),
+            free: free::<T>,
+            state: Mutex::new(State {
+                on_read: None,
+                on_write: None,
+                accesses: Vec::new(),
+            }),
+        });
+
+        let mut result = unsafe {
+            Self::from_ptr(inner.ptr as *mut T)
+        };
+        result.mock = Some(inner.clone());
+        (result, MockHandle {
+            inner,
+            _type: marker::PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MemoryMap;
+    use super::Access;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct Padded {
+        flag: u8,
+        value: u32,
+    }
+
+    #[test]
+    fn should_record_padded_struct() {
+        let (mut map, handle) = MemoryMap::<Padded>::mock(Padded {
+            flag: 1,
+            value: 2,
+        });
+        map.write(Padded {
+            flag: 3,
+            value: 4,
+        });
+        assert_eq!(map.read(), Padded {
+            flag: 3,
+            value: 4,
+        });
+
+        assert_eq!(handle.value().value, 4);
+        assert_eq!(handle.accesses(), [
+            Access::Write(Padded { flag: 3, value: 4 }),
+            Access::Read(Padded { flag: 3, value: 4 }),
+        ]);
+    }
+
+    #[test]
+    fn should_apply_hooks() {
+        let (mut map, handle) = MemoryMap::<u32>::mock(0);
+        handle.on_write(|val| val | 0x100);
+        handle.on_read(|val| val + 1);
+
+        map.write(1);
+        assert_eq!(handle.value(), 0x101);
+        assert_eq!(map.read(), 0x102);
+        assert_eq!(handle.accesses(), [Access::Write(0x101), Access::Read(0x102)]);
+
+        handle.clear();
+        assert!(handle.accesses().is_empty());
+    }
+}
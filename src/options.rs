@@ -0,0 +1,503 @@
+//! Memory map options
+
+use crate::{access, Error, ErrorKind, HugePageSize, MemoryMap, Protection};
+#[cfg(unix)]
+use crate::unix;
+#[cfg(windows)]
+use crate::win32;
+#[cfg(any(windows, all(unix, not(any(target_os = "linux", target_os = "android")))))]
+use crate::mmap::touch;
+
+#[derive(Clone, Copy, Debug)]
+///Builder of memory map, translating options to platform specific flags
+///
+///By default it creates shared anonymous mapping with read and write access.
+pub struct MmapOptions {
+    offset: u64,
+    prot: Protection,
+    shared: bool,
+    populate: bool,
+    no_reserve: bool,
+    address: usize,
+    huge: Option<HugePageSize>,
+    #[cfg(unix)]
+    fd: libc::c_int,
+    #[cfg(windows)]
+    handle: win32::HANDLE,
+}
+
+impl MmapOptions {
+    #[inline]
+    ///Creates default options.
+    pub const fn new() -> Self {
+        Self {
+            offset: 0,
+            prot: Protection(Protection::READ.0 | Protection::WRITE.0),
+            shared: true,
+            populate: false,
+            no_reserve: false,
+            address: 0,
+            huge: None,
+            #[cfg(unix)]
+            fd: -1,
+            #[cfg(windows)]
+            handle: win32::INVALID_HANDLE_VALUE,
+        }
+    }
+
+    #[inline]
+    ///Sets offset within file to start.
+    ///
+    ///It is not required to be page aligned.
+    pub const fn offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    #[inline]
+    ///Sets whether memory can be read.
+    pub const fn read(self, read: bool) -> Self {
+        self.set(Protection::READ, read)
+    }
+
+    #[inline]
+    ///Sets whether memory can be written.
+    pub const fn write(self, write: bool) -> Self {
+        self.set(Protection::WRITE, write)
+    }
+
+    #[inline]
+    ///Sets whether memory can be executed.
+    pub const fn exec(self, exec: bool) -> Self {
+        self.set(Protection::EXEC, exec)
+    }
+
+    #[inline]
+    ///Makes changes visible to other mappings of the same file and carried to the file.
+    pub const fn shared(mut self) -> Self {
+        self.shared = true;
+        self
+    }
+
+    #[inline]
+    ///Makes changes private to this mapping (copy on write).
+    pub const fn private(mut self) -> Self {
+        self.shared = false;
+        self
+    }
+
+    #[inline]
+    ///Sets whether to fault in whole mapping before it is returned, avoiding page faults on first access.
+    ///
+    ///On linux it is `MAP_POPULATE`, elsewhere every page is read after mapping, which requires read access.
+    pub const fn populate(mut self, populate: bool) -> Self {
+        self.populate = populate;
+        self
+    }
+
+    #[inline]
+    ///Sets whether to not reserve swap space for mapping, allowing to create mappings exceeding commit limit.
+    ///
+    ///Intended for sparse mappings, only fraction of which is ever accessed.
+    ///Tradeoff is that writing to page, when there is no memory left, kills program with `SIGSEGV` (or `SIGBUS`)
+    ///instead of failing to create mapping.
+    ///
+    ///On linux it is `MAP_NORESERVE`, which is ignored by kernel if overcommit is disabled (`vm.overcommit_memory = 2`).
+    ///Ignored on other platforms.
+    pub const fn no_reserve(mut self, no_reserve: bool) -> Self {
+        self.no_reserve = no_reserve;
+        self
+    }
+
+    #[inline]
+    ///Places mapping at `address`, which must be page aligned (allocation granularity on windows).
+    ///
+    ///On linux it is `MAP_FIXED_NOREPLACE` and on freebsd `MAP_FIXED | MAP_EXCL`, failing if range is already mapped.
+    ///Elsewhere it is `MAP_FIXED`, which silently replaces any existing mappings within range,
+    ///invalidating memory that might be still in use by program. Windows fails if range is not free.
+    ///
+    ///Pointer to data is equal to `address` if offset is page aligned.
+    ///Zero means no particular address, which is default.
+    pub const fn at_address(mut self, address: usize) -> Self {
+        self.address = address;
+        self
+    }
+
+    #[inline]
+    ///Backs mapping by huge pages of `size`, rounding length of mapping up to multiple of `size`.
+    ///
+    ///If there are no huge pages of `size` available, mapping fails with `NoHugePages` error instead of falling back to normal pages.
+    ///Only supported on linux for anonymous mappings or files on `hugetlbfs`,
+    ///and on macOS for anonymous mappings with 2 MiB pages, via `VM_FLAGS_SUPERPAGE_SIZE_2MB`.
+    ///Note that macOS provides superpages only on `x86_64`.
+    pub const fn huge_pages(mut self, size: HugePageSize) -> Self {
+        self.huge = Some(size);
+        self
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    ///Maps file descriptor `fd`, which remains owned by caller.
+    pub const fn fd(mut self, fd: libc::c_int) -> Self {
+        self.fd = fd;
+        self
+    }
+
+    #[cfg(windows)]
+    #[inline]
+    ///Maps file `handle`, which remains owned by caller.
+    pub const fn handle(mut self, handle: *mut core::ffi::c_void) -> Self {
+        self.handle = handle;
+        self
+    }
+
+    #[inline]
+    ///Maps anonymous memory, not backed by any file.
+    pub const fn anonymous(mut self) -> Self {
+        #[cfg(unix)]
+        {
+            self.fd = -1;
+        }
+        #[cfg(windows)]
+        {
+            self.handle = win32::INVALID_HANDLE_VALUE;
+        }
+        self
+    }
+
+    #[inline]
+    const fn set(mut self, flag: Protection, value: bool) -> Self {
+        self.prot = match value {
+            true => Protection(self.prot.0 | flag.0),
+            false => Protection(self.prot.0 & !flag.0),
+        };
+        self
+    }
+
+    #[inline]
+    fn validate<A: access::Access>(&self) -> Result<(), Error> {
+        //Write only pages are not supported by most platforms, which grant read access silently
+        let write_only = self.prot.contains(Protection::WRITE) && !self.prot.contains(Protection::READ);
+        let access = (A::READABLE && !self.prot.contains(Protection::READ)) || (A::WRITABLE && !self.prot.contains(Protection::WRITE));
+        //Superpages are only available via VM flags in place of file descriptor
+        #[cfg(target_os = "macos")]
+        let huge_fd = self.huge.is_some() && self.fd != -1;
+        #[cfg(not(target_os = "macos"))]
+        let huge_fd = false;
+        //Huge pages cannot be mapped from the middle
+        let huge_offset = match self.huge {
+            Some(huge) => !self.offset.is_multiple_of(huge.bytes() as u64),
+            None => false,
+        };
+
+        match write_only || access || huge_fd || huge_offset {
+            true => Err(Error::new(ErrorKind::InvalidOptions, 0)),
+            false => Ok(()),
+        }
+    }
+
+    ///Creates memory map of `T` with access `A`.
+    ///
+    ///It is up to user to ensure that file is valid to be mapped at offset.
+    ///
+    ///Returns `InvalidOptions` error, without attempting to map, if protection is write only,
+    ///if access `A` is not permitted by protection, if offset is not multiple of huge page size
+    ///or if huge pages are requested for file on macOS.
+    ///
+    ///On windows read access is always granted.
+    pub unsafe fn map<T, A: access::Access>(&self) -> Result<MemoryMap<T, A>, Error> {
+        self.validate::<A>()?;
+
+        #[cfg(unix)]
+        {
+            let mut flags = match self.shared {
+                true => libc::MAP_SHARED,
+                false => libc::MAP_PRIVATE,
+            };
+            if self.fd == -1 {
+                flags |= libc::MAP_ANON;
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            if self.populate {
+                flags |= libc::MAP_POPULATE;
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            if self.no_reserve {
+                flags |= libc::MAP_NORESERVE;
+            }
+
+            if self.address != 0 {
+                #[cfg(target_os = "linux")]
+                {
+                    flags |= libc::MAP_FIXED_NOREPLACE;
+                }
+                #[cfg(target_os = "freebsd")]
+                {
+                    flags |= libc::MAP_FIXED | libc::MAP_EXCL;
+                }
+                #[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+                {
+                    flags |= libc::MAP_FIXED;
+                }
+            }
+
+            #[cfg(target_os = "linux")]
+            let size = match self.huge {
+                Some(huge) => {
+                    flags |= libc::MAP_HUGETLB | huge.as_raw();
+                    core::mem::size_of::<T>().checked_next_multiple_of(huge.bytes()).ok_or(Error::too_large())?
+                },
+                None => core::mem::size_of::<T>(),
+            };
+            #[cfg(target_os = "macos")]
+            let size = match self.huge {
+                Some(HugePageSize::Size2MiB) => core::mem::size_of::<T>().checked_next_multiple_of(HugePageSize::Size2MiB.bytes()).ok_or(Error::too_large())?,
+                Some(_) => return Err(Error::unsupported()),
+                None => core::mem::size_of::<T>(),
+            };
+            #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+            let size = match self.huge {
+                Some(_) => return Err(Error::unsupported()),
+                None => core::mem::size_of::<T>(),
+            };
+
+            //Anonymous mapping on macOS takes VM flags in place of file descriptor
+            #[cfg(target_os = "macos")]
+            let fd = match self.huge {
+                Some(_) => unix::VM_FLAGS_SUPERPAGE_SIZE_2MB,
+                None => self.fd,
+            };
+            #[cfg(not(target_os = "macos"))]
+            let fd = self.fd;
+
+            let page = match self.huge {
+                Some(huge) => huge.bytes(),
+                None => unix::granularity(),
+            };
+            let map = unix::map_at(core::ptr::without_provenance_mut(self.address), self.offset, size, fd, self.prot.as_raw(), flags, page);
+            let map = map.map_err(|error| match (self.huge, error.raw_os_error()) {
+                (Some(_), libc::ENOMEM) => Error::new(ErrorKind::NoHugePages, libc::ENOMEM),
+                _ => error,
+            })?;
+            //Kernels prior to 4.17 treat unknown MAP_FIXED_NOREPLACE as hint
+            if self.address != 0 && !map.base.is_null() && map.base as usize != self.address {
+                libc::munmap(map.base, map.len);
+                return Err(Error::new(ErrorKind::MapFailed, libc::EEXIST));
+            }
+
+            let result = MemoryMap::from_mapping(map)?;
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            if self.populate && self.prot.contains(Protection::READ) {
+                touch(&result);
+            }
+            Ok(result)
+        }
+
+        #[cfg(windows)]
+        {
+            if self.huge.is_some() {
+                return Err(Error::unsupported());
+            }
+
+            let write = self.prot.contains(Protection::WRITE);
+            let exec = self.prot.contains(Protection::EXEC);
+            //Memory backed by paging file is not visible to anyone else, hence there is nothing to copy on write
+            let copy = write && !self.shared && self.handle != win32::INVALID_HANDLE_VALUE;
+
+            let protect = match (exec, write, copy) {
+                (false, false, _) => win32::PAGE_READONLY,
+                (false, true, false) => win32::PAGE_READWRITE,
+                (false, true, true) => win32::PAGE_WRITECOPY,
+                (true, false, _) => win32::PAGE_EXECUTE_READ,
+                (true, true, false) => win32::PAGE_EXECUTE_READWRITE,
+                (true, true, true) => win32::PAGE_EXECUTE_WRITECOPY,
+            };
+            let mut access = match (write, copy) {
+                (_, true) => win32::FILE_MAP_COPY,
+                (true, false) => win32::FILE_MAP_READ | win32::FILE_MAP_WRITE,
+                (false, false) => win32::FILE_MAP_READ,
+            };
+            if exec {
+                access |= win32::FILE_MAP_EXECUTE;
+            }
+
+            let map = win32::map_at(core::ptr::without_provenance_mut(self.address), self.offset, core::mem::size_of::<T>(), self.handle, protect, access)?;
+            let result = MemoryMap::from_mapping(map)?;
+            if self.populate {
+                touch(&result);
+            }
+            Ok(result)
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        Err(Error::unsupported())
+    }
+
+    #[inline]
+    ///Creates anonymous memory map of `T` with access `A`, regardless of file set previously.
+    ///
+    ///Same as [map](#method.map), options are validated before mapping.
+    pub unsafe fn map_anonymous<T, A: access::Access>(&self) -> Result<MemoryMap<T, A>, Error> {
+        self.anonymous().map()
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    ///Creates memory map of `T` with access `A` on file descriptor `fd` at `offset`.
+    ///
+    ///Descriptor remains owned by caller and can be closed once mapping is created.
+    ///Same as [map](#method.map), options are validated before mapping.
+    pub unsafe fn map_fd<T, A: access::Access>(&self, fd: libc::c_int, offset: u64) -> Result<MemoryMap<T, A>, Error> {
+        self.fd(fd).offset(offset).map()
+    }
+}
+
+impl Default for MmapOptions {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MmapOptions;
+    use crate::{ErrorKind, ReadOnly, ReadWrite, WriteOnly};
+
+    #[test]
+    fn should_reject_write_only_protection() {
+        let error = unsafe {
+            MmapOptions::new().read(false).map_anonymous::<u32, WriteOnly>().unwrap_err()
+        };
+        assert_eq!(error.kind(), ErrorKind::InvalidOptions);
+    }
+
+    #[test]
+    fn should_reject_access_not_permitted_by_protection() {
+        let error = unsafe {
+            MmapOptions::new().write(false).map_anonymous::<u32, ReadWrite>().unwrap_err()
+        };
+        assert_eq!(error.kind(), ErrorKind::InvalidOptions);
+
+        let error = unsafe {
+            MmapOptions::new().read(false).write(false).map_anonymous::<u32, ReadOnly>().unwrap_err()
+        };
+        assert_eq!(error.kind(), ErrorKind::InvalidOptions);
+
+        let map = unsafe {
+            MmapOptions::new().write(false).map_anonymous::<u32, ReadOnly>().expect("to map")
+        };
+        assert_eq!(map.read(), 0);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn should_reject_huge_pages_of_file() {
+        let error = unsafe {
+            MmapOptions::new().huge_pages(crate::HugePageSize::Size2MiB).fd(0).map::<u32, ReadWrite>().unwrap_err()
+        };
+        assert_eq!(error.kind(), ErrorKind::InvalidOptions);
+    }
+
+    #[test]
+    fn should_reject_offset_within_huge_page() {
+        let error = unsafe {
+            MmapOptions::new().huge_pages(crate::HugePageSize::Size2MiB).offset(4096).map::<u32, ReadWrite>().unwrap_err()
+        };
+        assert_eq!(error.kind(), ErrorKind::InvalidOptions);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn should_use_huge_page_granularity() {
+        let huge = crate::HugePageSize::Size2MiB;
+        let map = match crate::MemoryMap::<[u8; 4096]>::anonymous_huge(huge) {
+            Ok(map) => map,
+            //Huge pages are not reserved
+            Err(error) if error.kind() == ErrorKind::NoHugePages => return,
+            Err(error) => panic!("failed to map huge pages: {error}"),
+        };
+
+        assert_eq!(map.page_size, huge.bytes());
+        assert_eq!(map.len(), huge.bytes());
+        assert_eq!(map.base as usize % huge.bytes(), 0);
+        map.prefault();
+        map.flush_range(4000, 96).expect("to flush within huge page");
+    }
+
+    #[test]
+    fn should_populate_mapping() {
+        let map = unsafe {
+            MmapOptions::new().populate(true).map_anonymous::<[u8; 65536], ReadWrite>().expect("to map")
+        };
+        let pages = map.len() / crate::page_size();
+        assert!((0..pages).all(|page| unsafe { map.read_at::<u8>(page * crate::page_size()) } == 0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn should_populate_pages_resident() {
+        let map = unsafe {
+            MmapOptions::new().private().populate(true).map_anonymous::<[u8; 65536], ReadWrite>().expect("to map")
+        };
+        let mut residency = [0u8; 65536 / 4096];
+        let pages = map.len().div_ceil(crate::page_size());
+        assert_eq!(unsafe { libc::mincore(map.base, map.len(), residency.as_mut_ptr()) }, 0);
+        assert!(residency[..pages].iter().all(|page| page & 1 == 1));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn should_place_mapping_at_address() {
+        //Reservation and placement are done in child process, where no other thread can map freed range meanwhile
+        match unsafe { libc::fork() } {
+            -1 => panic!("fork failed"),
+            0 => unsafe {
+                let reserved = crate::MemoryMap::<u32>::try_anonymous();
+                let address = match reserved {
+                    Ok(ref map) => map.base as usize,
+                    Err(_) => libc::_exit(1),
+                };
+                //Range is occupied
+                match MmapOptions::new().at_address(address).map_anonymous::<u32, ReadWrite>() {
+                    Err(error) if error.kind() == ErrorKind::MapFailed => (),
+                    _ => libc::_exit(2),
+                }
+
+                drop(reserved);
+                match MmapOptions::new().at_address(address).map_anonymous::<u32, ReadWrite>() {
+                    Ok(map) if map.as_ptr() as usize == address => libc::_exit(0),
+                    _ => libc::_exit(3),
+                }
+            },
+            child => {
+                let mut status = 0;
+                assert_eq!(unsafe { libc::waitpid(child, &mut status, 0) }, child);
+                assert!(libc::WIFEXITED(status));
+                assert_eq!(libc::WEXITSTATUS(status), 0);
+            },
+        }
+    }
+
+    #[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+    #[test]
+    fn should_map_sparse_region_without_reserve() {
+        const LEN: usize = 64 << 30;
+
+        //Strict overcommit ignores `MAP_NORESERVE`
+        if std::fs::read_to_string("/proc/sys/vm/overcommit_memory").is_ok_and(|mode| mode.trim() == "2") {
+            return;
+        }
+
+        let mut map = unsafe {
+            MmapOptions::new().private().no_reserve(true).map_anonymous::<[u8; LEN], ReadWrite>().expect("to map")
+        };
+        assert_eq!(map.len(), LEN);
+        for offset in [0, LEN / 2, LEN - 8] {
+            unsafe {
+                map.write_at::<u64>(offset, offset as u64);
+                assert_eq!(map.read_at::<u64>(offset), offset as u64);
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! PCI device access via sysfs
+//!
+//!Maps BARs of PCI devices through `/sys/bus/pci/devices/*/resourceN` files, without need for `/dev/mem`.
+
+use core::{fmt, str};
+
+use crate::{Error, ErrorKind, MemoryMap};
+use crate::sysfs::{self, Path};
+
+//Flag of memory BAR within sysfs `resource` file
+const IORESOURCE_MEM: u64 = 0x200;
+//Number of BARs within type 0 configuration header
+const BAR_NUM: u8 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Address of PCI function
+pub struct PciAddress {
+    ///PCI domain (segment)
+    pub domain: u16,
+    ///Bus number
+    pub bus: u8,
+    ///Device number
+    pub device: u8,
+    ///Function number
+    pub function: u8,
+}
+
+impl PciAddress {
+    #[inline]
+    ///Creates new address
+    pub const fn new(domain: u16, bus: u8, device: u8, function: u8) -> Self {
+        Self {
+            domain,
+            bus,
+            device,
+            function,
+        }
+    }
+}
+
+impl fmt::Display for PciAddress {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_fmt(format_args!("{:04x}:{:02x}:{:02x}.{:x}", self.domain, self.bus, self.device, self.function))
+    }
+}
+
+///Returns size of memory BAR `bar` of device at `address`.
+///
+///Fails with `NotMappable` error if BAR is not implemented or is IO port BAR.
+pub fn bar_size(address: PciAddress, bar: u8) -> Result<u64, Error> {
+    if bar >= BAR_NUM {
+        return Err(Error::new(ErrorKind::NotMappable, libc::EINVAL));
+    }
+
+    let path = Path::new(format_args!("/sys/bus/pci/devices/{}/resource", address));
+    let mut buf = [0u8; 1024];
+    let text = sysfs::read(&path, &mut buf)?;
+
+    //Each line is `start end flags` in hex
+    let mut parts = text.lines().nth(bar as usize).into_iter().flat_map(str::split_whitespace).map(sysfs::parse_hex);
+    match (parts.next().flatten(), parts.next().flatten(), parts.next().flatten()) {
+        (Some(start), Some(end), Some(flags)) if flags & IORESOURCE_MEM != 0 && end >= start => Ok(end - start + 1),
+        (Some(_), Some(_), Some(_)) => Err(Error::new(ErrorKind::NotMappable, 0)),
+        _ => Err(Error::new(ErrorKind::InvalidAttribute, libc::EINVAL)),
+    }
+}
+
+impl<T> MemoryMap<T> {
+    ///Creates memory mapping of memory BAR `bar` of PCI device at `address`, starting at `offset` within BAR
+    ///
+    ///Resource file is opened with `O_RDWR | O_SYNC` and closed once mapping is created.
+    ///
+    ///Errors:
+    ///
+    ///- `NotFound` if there is no such device.
+    ///- `NotMappable` if BAR is not implemented or is IO port BAR.
+    ///- `PermissionDenied` if user lacks permission to access device.
+    ///- `TooSmall` if `offset + size_of::<T>()` exceeds size of BAR.
+    pub unsafe fn pci_resource(address: PciAddress, bar: u8, offset: u64) -> Result<Self, Error> {
+        let size = bar_size(address, bar)?;
+        match offset.checked_add(core::mem::size_of::<T>() as u64) {
+            Some(end) if end <= size => (),
+            _ => return Err(Error::new(ErrorKind::TooSmall, 0)),
+        }
+
+        //Device is known to exist at this point, hence missing file means kernel does not permit mapping it
+        let path = Path::new(format_args!("/sys/bus/pci/devices/{}/resource{}", address, bar));
+        let fd = sysfs::open(&path, libc::O_RDWR | libc::O_SYNC).map_err(|error| match error.kind() {
+            ErrorKind::NotFound => Error::new(ErrorKind::NotMappable, libc::ENOENT),
+            _ => error,
+        })?;
+        let result = Self::open_file_raw64(offset, fd, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED);
+        libc::close(fd);
+        result
+    }
+}
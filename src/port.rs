@@ -0,0 +1,113 @@
+//! Port-mapped IO via `/dev/port`
+//!
+//!Legacy IO ports (e.g. `0x3F8` UART) cannot be memory mapped, hence they are accessed via `pread`/`pwrite` at port offset.
+
+use crate::{Error, ErrorKind};
+
+//Number of IO ports
+const PORT_NUM: u32 = 0x10000;
+
+///Handle to `/dev/port`, closed on drop
+///
+///Note that kernel accesses ports byte by byte, therefore 16 and 32 bit accesses are performed as sequence of 8 bit accesses of consecutive ports.
+pub struct PortIo {
+    fd: libc::c_int,
+}
+
+impl PortIo {
+    ///Opens `/dev/port`, which requires root (`CAP_SYS_RAWIO`).
+    ///
+    ///Returns `Unsupported` error on platforms other than linux on x86.
+    pub fn open() -> Result<Self, Error> {
+        #[cfg(all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64")))]
+        {
+            let fd = unsafe {
+                libc::open(c"/dev/port".as_ptr(), libc::O_RDWR | libc::O_CLOEXEC)
+            };
+            match fd {
+                -1 => Err(Error::last(ErrorKind::OpenFailed)),
+                fd => Ok(Self {
+                    fd,
+                }),
+            }
+        }
+
+        #[cfg(not(all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64"))))]
+        Err(Error::unsupported())
+    }
+
+    //Rejects access of `len` bytes at `port`, going beyond last port
+    #[inline]
+    fn check(port: u32, len: usize) -> Result<(), Error> {
+        match port.checked_add(len as u32) {
+            Some(end) if end <= PORT_NUM => Ok(()),
+            _ => Err(Error::new(ErrorKind::PortFailed, libc::EINVAL)),
+        }
+    }
+
+    fn read<const N: usize>(&self, port: u32) -> Result<[u8; N], Error> {
+        Self::check(port, N)?;
+
+        let mut buf = [0u8; N];
+        match unsafe { libc::pread(self.fd, buf.as_mut_ptr() as _, N, port as libc::off_t) } {
+            -1 => Err(Error::last(ErrorKind::PortFailed)),
+            len if len as usize == N => Ok(buf),
+            _ => Err(Error::new(ErrorKind::PortFailed, libc::EIO)),
+        }
+    }
+
+    fn write<const N: usize>(&self, port: u32, buf: [u8; N]) -> Result<(), Error> {
+        Self::check(port, N)?;
+
+        match unsafe { libc::pwrite(self.fd, buf.as_ptr() as _, N, port as libc::off_t) } {
+            -1 => Err(Error::last(ErrorKind::PortFailed)),
+            len if len as usize == N => Ok(()),
+            _ => Err(Error::new(ErrorKind::PortFailed, libc::EIO)),
+        }
+    }
+
+    #[inline]
+    ///Reads byte from `port`
+    pub fn read_u8(&self, port: u32) -> Result<u8, Error> {
+        self.read::<1>(port).map(u8::from_le_bytes)
+    }
+
+    #[inline]
+    ///Writes byte to `port`
+    pub fn write_u8(&self, port: u32, val: u8) -> Result<(), Error> {
+        self.write(port, val.to_le_bytes())
+    }
+
+    #[inline]
+    ///Reads 16 bit value from `port` and `port + 1`
+    pub fn read_u16(&self, port: u32) -> Result<u16, Error> {
+        self.read::<2>(port).map(u16::from_le_bytes)
+    }
+
+    #[inline]
+    ///Writes 16 bit value to `port` and `port + 1`
+    pub fn write_u16(&self, port: u32, val: u16) -> Result<(), Error> {
+        self.write(port, val.to_le_bytes())
+    }
+
+    #[inline]
+    ///Reads 32 bit value from `port` to `port + 3`
+    pub fn read_u32(&self, port: u32) -> Result<u32, Error> {
+        self.read::<4>(port).map(u32::from_le_bytes)
+    }
+
+    #[inline]
+    ///Writes 32 bit value to `port` to `port + 3`
+    pub fn write_u32(&self, port: u32, val: u32) -> Result<(), Error> {
+        self.write(port, val.to_le_bytes())
+    }
+}
+
+impl Drop for PortIo {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
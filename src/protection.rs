@@ -0,0 +1,131 @@
+//! Memory protection
+
+use core::{fmt, ops};
+
+use crate::access;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Memory protection, combined via `|`
+pub struct Protection(pub(crate) u8);
+
+impl Protection {
+    ///Pages cannot be accessed
+    pub const NONE: Self = Self(0);
+    ///Pages can be read
+    pub const READ: Self = Self(1);
+    ///Pages can be written
+    pub const WRITE: Self = Self(2);
+    ///Pages can be executed
+    pub const EXEC: Self = Self(4);
+
+    #[inline]
+    ///Returns whether all of `other` is permitted.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[inline]
+    ///Returns protection permitting access `A`
+    pub(crate) const fn of<A: access::Access>() -> Self {
+        let mut result = Self::NONE;
+        if A::READABLE {
+            result.0 |= Self::READ.0;
+        }
+        if A::WRITABLE {
+            result.0 |= Self::WRITE.0;
+        }
+        result
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    pub(crate) const fn from_raw(prot: libc::c_int) -> Self {
+        let mut result = Self::NONE;
+        if prot & libc::PROT_READ != 0 {
+            result.0 |= Self::READ.0;
+        }
+        if prot & libc::PROT_WRITE != 0 {
+            result.0 |= Self::WRITE.0;
+        }
+        if prot & libc::PROT_EXEC != 0 {
+            result.0 |= Self::EXEC.0;
+        }
+        result
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    pub(crate) const fn as_raw(self) -> libc::c_int {
+        let mut result = libc::PROT_NONE;
+        if self.contains(Self::READ) {
+            result |= libc::PROT_READ;
+        }
+        if self.contains(Self::WRITE) {
+            result |= libc::PROT_WRITE;
+        }
+        if self.contains(Self::EXEC) {
+            result |= libc::PROT_EXEC;
+        }
+        result
+    }
+
+    #[cfg(windows)]
+    #[inline]
+    ///Returns page protection, with write implying read as windows has no write only pages.
+    pub(crate) const fn as_raw(self) -> u32 {
+        use crate::win32;
+
+        match (self.contains(Self::READ) || self.contains(Self::WRITE), self.contains(Self::WRITE), self.contains(Self::EXEC)) {
+            (false, _, false) => win32::PAGE_NOACCESS,
+            (true, false, false) => win32::PAGE_READONLY,
+            (true, true, false) => win32::PAGE_READWRITE,
+            (false, _, true) => win32::PAGE_EXECUTE,
+            (true, false, true) => win32::PAGE_EXECUTE_READ,
+            (true, true, true) => win32::PAGE_EXECUTE_READWRITE,
+        }
+    }
+
+    #[cfg(windows)]
+    #[inline]
+    ///Returns protection of page protection, ignoring modifiers such as `PAGE_NOCACHE`.
+    pub(crate) const fn from_raw(protect: u32) -> Self {
+        use crate::win32;
+
+        match protect & 0xff {
+            win32::PAGE_READONLY => Self::READ,
+            win32::PAGE_READWRITE | win32::PAGE_WRITECOPY => Self(Self::READ.0 | Self::WRITE.0),
+            win32::PAGE_EXECUTE => Self::EXEC,
+            win32::PAGE_EXECUTE_READ => Self(Self::READ.0 | Self::EXEC.0),
+            win32::PAGE_EXECUTE_READWRITE | win32::PAGE_EXECUTE_WRITECOPY => Self(Self::READ.0 | Self::WRITE.0 | Self::EXEC.0),
+            _ => Self::NONE,
+        }
+    }
+}
+
+//Formatted as `RWX`, with `-` in place of missing access, similar to `/proc/self/maps`
+impl fmt::Display for Protection {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |prot: Self, name: char| match self.contains(prot) {
+            true => name,
+            false => '-',
+        };
+        write!(fmt, "{}{}{}", flag(Self::READ, 'R'), flag(Self::WRITE, 'W'), flag(Self::EXEC, 'X'))
+    }
+}
+
+impl ops::BitOr for Protection {
+    type Output = Self;
+
+    #[inline(always)]
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl ops::BitOrAssign for Protection {
+    #[inline(always)]
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
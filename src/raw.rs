@@ -0,0 +1,348 @@
+//! Raw pointer to memory map
+
+use core::{fmt, marker};
+
+use crate::{barrier, volatile};
+use crate::access::{self, ReadWrite};
+
+///Memory mapped raw pointer
+///
+///Access `A` determines whether pointer can be read and/or written.
+pub struct RawPtr<'a, T, A = ReadWrite> {
+    ///Pointer
+    pub ptr: *mut T,
+    _lifetime: marker::PhantomData<(&'a mut T, A)>,
+}
+
+impl<'a, T, A> RawPtr<'a, T, A> {
+    #[inline(always)]
+    pub(crate) const fn new(ptr: *mut T) -> Self {
+        Self {
+            ptr,
+            _lifetime: marker::PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    ///Creates pointer to memory at `ptr`.
+    ///
+    ///It is up to user to ensure `ptr` is valid for access `A` for lifetime `'a`.
+    pub const unsafe fn from_ptr(ptr: *mut T) -> Self {
+        Self::new(ptr)
+    }
+
+    #[inline(always)]
+    ///Returns pointer, same as `ptr` field.
+    pub const fn get(&self) -> *mut T {
+        self.ptr
+    }
+
+    #[inline(always)]
+    ///Returns read only pointer, borrowing this one.
+    pub const fn as_const(&self) -> RawPtrConst<'_, T, A> {
+        RawPtrConst::new(self.ptr)
+    }
+
+    #[inline(always)]
+    ///Offsets pointer by `count` elements of `T`.
+    ///
+    ///It is up to user to ensure that resulting pointer is within the same memory map.
+    pub const unsafe fn offset(self, count: isize) -> Self {
+        Self::new(self.ptr.offset(count))
+    }
+
+    #[inline(always)]
+    ///Offsets pointer by `count` bytes.
+    ///
+    ///It is up to user to ensure that resulting pointer is within the same memory map and is aligned for `T`.
+    pub const unsafe fn add_bytes(self, count: usize) -> Self {
+        Self::new(self.ptr.byte_add(count))
+    }
+
+    #[inline(always)]
+    ///Converts to pointer to `U`.
+    ///
+    ///It is up to user to ensure that `U` fits within memory map, is aligned and that data is valid `U`.
+    pub const unsafe fn cast<U>(self) -> RawPtr<'a, U, A> {
+        RawPtr::new(self.ptr as *mut U)
+    }
+}
+
+impl<'a, T, A: access::Readable> RawPtr<'a, T, A> {
+    #[inline]
+    ///Reads data
+    pub fn read(&self) -> T where T: Copy {
+        unsafe {
+            volatile::read(self.ptr)
+        }
+    }
+
+    #[inline]
+    ///Reads data, ordering it before any subsequent reads of normal memory.
+    ///
+    ///Intended for reading status of device before accessing memory written by it, e.g. via DMA.
+    ///Barrier is emitted after read: `dmb oshld` on `aarch64`, `fence i, r` on `riscv` and acquire fence on other architectures except `x86`,
+    ///where only compiler is prevented from reordering, as CPU does not reorder such reads.
+    pub fn read_acquire(&self) -> T where T: Copy {
+        let val = self.read();
+        barrier::read();
+        val
+    }
+}
+
+impl<'a, T, A: access::Writable> RawPtr<'a, T, A> {
+    #[inline]
+    ///Writes data
+    pub fn write(&mut self, val: T) {
+        unsafe {
+            volatile::write(self.ptr, val)
+        }
+    }
+
+    #[inline]
+    ///Writes data, ordering it after any preceding writes to normal memory.
+    ///
+    ///Intended for notifying device about memory prepared for it, e.g. writing doorbell after DMA descriptors.
+    ///Barrier is emitted before write: `dmb oshst` on `aarch64`, `fence w, o` on `riscv` and release fence on other architectures except `x86`,
+    ///where only compiler is prevented from reordering, as CPU does not reorder such writes.
+    pub fn write_release(&mut self, val: T) {
+        barrier::write();
+        self.write(val);
+    }
+}
+
+impl<'a, T, A: access::Readable + access::Writable> RawPtr<'a, T, A> {
+    #[inline]
+    ///Gives callback to accept value to return modified value to write.
+    pub fn read_and_write<F: FnOnce(T) -> T>(&mut self, cb: F) where T: Copy {
+        let new = cb(self.read());
+        self.write(new);
+    }
+
+    #[inline]
+    ///Writes `val`, returning previous value.
+    ///
+    ///It is volatile read followed by volatile write, which is not atomic with respect to device or other CPUs.
+    pub fn replace(&mut self, val: T) -> T where T: Copy {
+        let old = self.read();
+        self.write(val);
+        old
+    }
+
+    #[inline]
+    ///Gives callback to accept value to return modified value to write along with result, which is returned.
+    ///
+    ///Same as [read_and_write](#method.read_and_write), it is not atomic with respect to device or other CPUs.
+    pub fn update<R, F: FnOnce(T) -> (T, R)>(&mut self, cb: F) -> R where T: Copy {
+        let (new, result) = cb(self.read());
+        self.write(new);
+        result
+    }
+}
+
+impl<'a, T, A> fmt::Pointer for RawPtr<'a, T, A> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.ptr, fmt)
+    }
+}
+
+impl<'a, T, A> fmt::Debug for RawPtr<'a, T, A> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.ptr, fmt)
+    }
+}
+
+unsafe impl<'a, T: Send, A> Send for RawPtr<'a, T, A> {
+}
+
+unsafe impl<'a, T: Sync, A> Sync for RawPtr<'a, T, A> {
+}
+
+///Memory mapped raw pointer, which cannot be written
+///
+///Same as shared reference, it can be copied freely.
+pub struct RawPtrConst<'a, T, A = ReadWrite> {
+    ptr: *const T,
+    _lifetime: marker::PhantomData<(&'a T, A)>,
+}
+
+impl<'a, T, A> RawPtrConst<'a, T, A> {
+    #[inline(always)]
+    pub(crate) const fn new(ptr: *const T) -> Self {
+        Self {
+            ptr,
+            _lifetime: marker::PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    ///Creates pointer to memory at `ptr`.
+    ///
+    ///It is up to user to ensure `ptr` is valid for access `A` for lifetime `'a`.
+    pub const unsafe fn from_ptr(ptr: *const T) -> Self {
+        Self::new(ptr)
+    }
+
+    #[inline(always)]
+    ///Returns pointer
+    pub const fn get(&self) -> *const T {
+        self.ptr
+    }
+}
+
+impl<'a, T, A: access::Readable> RawPtrConst<'a, T, A> {
+    #[inline]
+    ///Reads data
+    pub fn read(&self) -> T where T: Copy {
+        unsafe {
+            volatile::read(self.ptr)
+        }
+    }
+}
+
+impl<'a, T, A> Clone for RawPtrConst<'a, T, A> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T, A> Copy for RawPtrConst<'a, T, A> {
+}
+
+impl<'a, T, A> fmt::Pointer for RawPtrConst<'a, T, A> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.ptr, fmt)
+    }
+}
+
+impl<'a, T, A> fmt::Debug for RawPtrConst<'a, T, A> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.ptr, fmt)
+    }
+}
+
+//Same as shared reference, it can only be sent if `T` can be shared
+unsafe impl<'a, T: Sync, A> Send for RawPtrConst<'a, T, A> {
+}
+
+unsafe impl<'a, T: Sync, A> Sync for RawPtrConst<'a, T, A> {
+}
+
+///Memory mapped raw slice of `len` elements
+///
+///Same as [RawPtr](struct.RawPtr.html), it borrows memory map, while every element is accessed via volatile read or write.
+pub struct RawSlice<'a, T, A = ReadWrite> {
+    ptr: *mut T,
+    len: usize,
+    _lifetime: marker::PhantomData<(&'a mut [T], A)>,
+}
+
+impl<'a, T, A> RawSlice<'a, T, A> {
+    #[inline(always)]
+    pub(crate) const fn new(ptr: *mut T, len: usize) -> Self {
+        Self {
+            ptr,
+            len,
+            _lifetime: marker::PhantomData,
+        }
+    }
+
+    #[inline(always)]
+    ///Creates slice of `len` elements at `ptr`.
+    ///
+    ///It is up to user to ensure `ptr` is valid for access `A` to `len` elements for lifetime `'a`.
+    pub const unsafe fn from_raw_parts(ptr: *mut T, len: usize) -> Self {
+        Self::new(ptr, len)
+    }
+
+    #[inline(always)]
+    ///Returns pointer to first element
+    pub const fn get_ptr(&self) -> *mut T {
+        self.ptr
+    }
+
+    #[inline(always)]
+    ///Returns number of elements
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline(always)]
+    ///Returns whether there are no elements
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<'a, T: Copy, A: access::Readable> RawSlice<'a, T, A> {
+    #[inline]
+    ///Reads element at `idx`
+    ///
+    ///## Panics
+    ///
+    ///If `idx` is out of bounds.
+    pub fn read_at(&self, idx: usize) -> T {
+        assert!(idx < self.len, "index {} is out of bounds for slice of {} elements", idx, self.len);
+        unsafe {
+            volatile::read(self.ptr.add(idx))
+        }
+    }
+
+    #[inline]
+    ///Reads element at `idx`, returning `None` if it is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<T> {
+        match idx < self.len {
+            true => Some(unsafe {
+                volatile::read(self.ptr.add(idx))
+            }),
+            false => None,
+        }
+    }
+}
+
+impl<'a, T: Copy, A: access::Writable> RawSlice<'a, T, A> {
+    #[inline]
+    ///Writes element at `idx`
+    ///
+    ///## Panics
+    ///
+    ///If `idx` is out of bounds.
+    pub fn write_at(&mut self, idx: usize, val: T) {
+        assert!(idx < self.len, "index {} is out of bounds for slice of {} elements", idx, self.len);
+        unsafe {
+            volatile::write(self.ptr.add(idx), val)
+        }
+    }
+
+    #[inline]
+    ///Writes element at `idx`, returning `val` back if it is out of bounds.
+    pub fn set(&mut self, idx: usize, val: T) -> Result<(), T> {
+        match idx < self.len {
+            true => {
+                unsafe {
+                    volatile::write(self.ptr.add(idx), val)
+                }
+                Ok(())
+            },
+            false => Err(val),
+        }
+    }
+}
+
+impl<'a, T, A> fmt::Debug for RawSlice<'a, T, A> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RawSlice").field("ptr", &self.ptr).field("len", &self.len).finish()
+    }
+}
+
+unsafe impl<'a, T: Send, A> Send for RawSlice<'a, T, A> {
+}
+
+unsafe impl<'a, T: Sync, A> Sync for RawSlice<'a, T, A> {
+}
@@ -0,0 +1,397 @@
+//! Register block definition
+
+#[macro_export]
+///Defines register block over memory map, with typed pointer to every register.
+///
+///Each register is `name: type @ byte_offset`, where offset is relative to start of block and type is any type, including path or generic one.
+///It generates struct, generic over access `A`, wrapping `MemoryMap<[u8; SIZE], A>` with:
+///
+///- `SIZE` - size of block in bytes, that is end of furthest register.
+///- `ALIGN` - alignment of block, that is maximum alignment of its registers.
+///- `new(map)` - creates block over memory map of it, panicking if it is not aligned to `ALIGN`.
+///- `into_inner(self)` - returns underlying memory map.
+///- `name(&mut self) -> RawPtr<'_, type, A>` per register, which is read/written via volatile access.
+///
+///Registers are checked at compile time to be aligned and to not overlap each other,
+///while memory map is checked by `new` to be aligned for every register.
+///
+///## Usage
+///
+///```
+///use dev_mmio::{mmio_registers, MemoryMap};
+///
+///mmio_registers! {
+///    ///Timer peripheral
+///    pub struct Timer {
+///        ///Control register
+///        ctrl: u32 @ 0x00,
+///        ///Value to start counting down from
+///        load: u32 @ 0x04,
+///        ///Current value
+///        value: u32 @ 0x08,
+///    }
+///}
+///
+///assert_eq!(Timer::<dev_mmio::ReadWrite>::SIZE, 12);
+///assert_eq!(Timer::<dev_mmio::ReadWrite>::ALIGN, 4);
+///let mut timer = Timer::new(MemoryMap::try_anonymous().expect("to map"));
+///timer.load().write(1000);
+///timer.ctrl().write(1);
+///assert_eq!(timer.load().read(), 1000);
+///assert_eq!(timer.value().read(), 0);
+///```
+///
+///Overlapping registers fail to compile:
+///
+///```compile_fail
+///dev_mmio::mmio_registers! {
+///    struct Regs {
+///        status: u32 @ 0x00,
+///        data: u16 @ 0x02,
+///    }
+///}
+///```
+macro_rules! mmio_registers {
+    ($(#[$meta:meta])* $vis:vis struct $name:ident { $($regs:tt)* }) => {
+        $crate::mmio_registers!(@parse [$(#[$meta])*] [$vis] $name [] $($regs)*);
+    };
+    //Every register is parsed
+    (@parse [$(#[$meta:meta])*] [$vis:vis] $name:ident [$([$(#[$reg_meta:meta])*] $reg:ident [$($ty:tt)+] ($offset:expr))*]) => {
+        $crate::__register_block!(@struct [$(#[$meta])*] [$vis] $name [] [$($offset => $($ty)+),*]);
+
+        impl<A: $crate::access::Access> $name<A> {
+            $(
+                #[inline]
+                $(#[$reg_meta])*
+                pub fn $reg(&mut self) -> $crate::RawPtr<'_, $($ty)+, A> {
+                    unsafe {
+                        $crate::RawPtr::from_ptr((self.map.as_mut_ptr() as *mut u8).add($offset) as *mut $($ty)+)
+                    }
+                }
+            )*
+        }
+
+        const _: () = $crate::__register_block!(@check $crate::__register_block!(@size [] $($offset => $($ty)+),*), $($offset => $($ty)+ [rw]),*);
+    };
+    (@parse $meta:tt $vis:tt $name:ident [$($done:tt)*] $(#[$reg_meta:meta])* $reg:ident: $($rest:tt)+) => {
+        $crate::mmio_registers!(@ty $meta $vis $name [$($done)*] [$(#[$reg_meta])*] $reg [] $($rest)+);
+    };
+    //Type is every token up to `@`, as `ty` cannot be followed by it
+    (@ty $meta:tt $vis:tt $name:ident [$($done:tt)*] $reg_meta:tt $reg:ident [$($ty:tt)+] @ $offset:expr $(, $($rest:tt)*)?) => {
+        $crate::mmio_registers!(@parse $meta $vis $name [$($done)* $reg_meta $reg [$($ty)+] ($offset)] $($($rest)*)?);
+    };
+    (@ty $meta:tt $vis:tt $name:ident $done:tt $reg_meta:tt $reg:ident [$($ty:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::mmio_registers!(@ty $meta $vis $name $done $reg_meta $reg [$($ty)* $next] $($rest)*);
+    };
+}
+
+#[macro_export]
+///Returns pointer to field of memory map data, accessing only this field on `read` and `write`.
+///
+///Accepts `map.field` for memory map in local variable, or `expr => field` for any other expression.
+///Nested fields are allowed, e.g. `map.timer.ctrl`. Bounds and alignment of field are checked via
+///[MemoryMap::project](struct.MemoryMap.html#method.project).
+///
+///## Usage
+///
+///```
+///use dev_mmio::{project, MemoryMap};
+///
+///#[repr(C)]
+///#[derive(Clone, Copy)]
+///struct Regs {
+///    ctrl: u32,
+///    status: u32,
+///    data: u32,
+///}
+///
+///let mut map = MemoryMap::<Regs>::try_anonymous().expect("to map");
+///project!(map.status).write(1);
+///assert_eq!(project!(map.status).read(), 1);
+///assert_eq!(project!(map.ctrl).read(), 0);
+///```
+macro_rules! project {
+    ($map:ident . $($field:tt).+) => {
+        $crate::project!($map => $($field).+)
+    };
+    ($map:expr => $($field:tt).+) => {
+        $map.project(|ptr| unsafe {
+            ::core::ptr::addr_of_mut!((*ptr).$($field).+)
+        })
+    };
+}
+
+#[cfg(feature = "macros")]
+#[macro_export]
+///Defines register block over memory map, with getter and/or setter for every register according to its access.
+///
+///Each register is `byte_offset => accessors: type [access]`, where offset is literal and access is one of:
+///
+///- `ro` - read only, accessors is name of getter `fn(&self) -> type`.
+///- `wo` - write only, accessors is name of setter `fn(&mut self, type)`.
+///- `rw` - read and write, accessors are names of getter and setter separated by `/`, e.g. `ctrl / set_ctrl`.
+///
+///Getters are available when access `A` of block is readable, while setters when it is writable.
+///Every access is volatile.
+///
+///Size of block in bytes can be specified in parentheses after name, otherwise it is end of furthest register.
+///It generates struct, generic over access `A`, wrapping `MemoryMap<[u8; SIZE], A>` with `SIZE`, `ALIGN`, `new(map)` and `into_inner(self)`,
+///same as [mmio_registers](macro.mmio_registers.html).
+///
+///Registers are checked at compile time to be aligned, to fit within block and to not overlap each other,
+///except read only and write only registers, which may share offset as it is common for hardware to have different registers for reading and writing.
+///
+///## Usage
+///
+///```
+///use dev_mmio::{register_block, MemoryMap};
+///
+///register_block! {
+///    ///16550 UART
+///    pub struct Uart(8) {
+///        ///Receiver buffer
+///        0x00 => rbr: u8 [ro],
+///        ///Transmitter holding
+///        0x00 => set_thr: u8 [wo],
+///        ///Interrupt enable
+///        0x01 => ier / set_ier: u8 [rw],
+///        ///Interrupt identification
+///        0x02 => iir: u8 [ro],
+///        ///FIFO control
+///        0x02 => set_fcr: u8 [wo],
+///        ///Line control
+///        0x03 => lcr / set_lcr: u8 [rw],
+///        ///Modem control
+///        0x04 => mcr / set_mcr: u8 [rw],
+///        ///Line status
+///        0x05 => lsr: u8 [ro],
+///        ///Modem status
+///        0x06 => msr: u8 [ro],
+///        ///Scratch
+///        0x07 => scr / set_scr: u8 [rw],
+///    }
+///}
+///
+///const THR_EMPTY: u8 = 1 << 5;
+///
+///assert_eq!(Uart::<dev_mmio::ReadWrite>::SIZE, 8);
+///let mut uart = Uart::new(MemoryMap::try_anonymous().expect("to map"));
+/////8 data bits, no parity, 1 stop bit
+///uart.set_lcr(0x03);
+///uart.set_fcr(0x07);
+///uart.set_ier(0);
+///assert_eq!(uart.lcr(), 0x03);
+///
+///if uart.lsr() & THR_EMPTY == 0 {
+///    for byte in b"hello" {
+///        uart.set_thr(*byte);
+///    }
+///}
+///```
+///
+///Registers outside of block fail to compile:
+///
+///```compile_fail
+///dev_mmio::register_block! {
+///    struct Regs(4) {
+///        0x00 => status: u32 [ro],
+///        0x04 => data / set_data: u32 [rw],
+///    }
+///}
+///```
+macro_rules! register_block {
+    ($(#[$meta:meta])* $vis:vis struct $name:ident $(($size:expr))? { $($(#[$reg_meta:meta])* $offset:literal => $first:ident $(/ $second:ident)?: $ty:ty [$access:ident]),* $(,)? }) => {
+        $crate::__register_block!(@struct [$(#[$meta])*] [$vis] $name [$($size)?] [$($offset => $ty),*]);
+
+        $(
+            $crate::register_block!(@reg $name [$(#[$reg_meta])*] $offset, $ty, $access, $first $(, $second)?);
+        )*
+
+        const _: () = $crate::__register_block!(@check $crate::__register_block!(@size [$($size)?] $($offset => $ty),*), $($offset => $ty [$access]),*);
+    };
+    (@reg $name:ident [$($reg_meta:tt)*] $offset:expr, $ty:ty, ro, $get:ident) => {
+        impl<A: $crate::access::Readable> $name<A> {
+            #[inline]
+            $($reg_meta)*
+            pub fn $get(&self) -> $ty {
+                unsafe {
+                    $crate::RawPtrConst::<$ty, A>::from_ptr((self.map.as_ptr() as *const u8).add($offset) as *const $ty).read()
+                }
+            }
+        }
+    };
+    (@reg $name:ident [$($reg_meta:tt)*] $offset:expr, $ty:ty, wo, $set:ident) => {
+        impl<A: $crate::access::Writable> $name<A> {
+            #[inline]
+            $($reg_meta)*
+            pub fn $set(&mut self, val: $ty) {
+                unsafe {
+                    $crate::RawPtr::<$ty, A>::from_ptr((self.map.as_mut_ptr() as *mut u8).add($offset) as *mut $ty).write(val)
+                }
+            }
+        }
+    };
+    (@reg $name:ident [$($reg_meta:tt)*] $offset:expr, $ty:ty, rw, $get:ident, $set:ident) => {
+        $crate::register_block!(@reg $name [$($reg_meta)*] $offset, $ty, ro, $get);
+        $crate::register_block!(@reg $name [$($reg_meta)*] $offset, $ty, wo, $set);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+///Implementation of register block macros, not part of public API
+macro_rules! __register_block {
+    (@struct [$(#[$meta:meta])*] [$vis:vis] $name:ident [$($size:expr)?] [$($offset:expr => $ty:ty),*]) => {
+        $(#[$meta])*
+        $vis struct $name<A = $crate::ReadWrite> {
+            map: $crate::MemoryMap<[u8; $crate::__register_block!(@size [$($size)?] $($offset => $ty),*)], A>,
+        }
+
+        impl<A: $crate::access::Access> $name<A> {
+            ///Size of register block in bytes
+            pub const SIZE: usize = $crate::__register_block!(@size [$($size)?] $($offset => $ty),*);
+            ///Alignment of register block, that is maximum alignment of its registers
+            pub const ALIGN: usize = $crate::__register_block!(@align $($ty),*);
+
+            #[inline]
+            ///Creates register block over memory map of it
+            ///
+            ///## Panics
+            ///
+            ///If memory map is not aligned to [ALIGN](#associatedconstant.ALIGN).
+            pub fn new(map: $crate::MemoryMap<[u8; $crate::__register_block!(@size [$($size)?] $($offset => $ty),*)], A>) -> Self {
+                assert!(map.as_ptr() as usize % Self::ALIGN == 0, "register block is not aligned");
+                Self {
+                    map,
+                }
+            }
+
+            #[inline]
+            ///Returns underlying memory map
+            pub fn into_inner(self) -> $crate::MemoryMap<[u8; $crate::__register_block!(@size [$($size)?] $($offset => $ty),*)], A> {
+                self.map
+            }
+        }
+    };
+    (@size [$size:expr] $($offset:expr => $ty:ty),*) => {
+        $size
+    };
+    (@size [] $($offset:expr => $ty:ty),*) => {{
+        let mut size = 0usize;
+        $(
+            let end = $offset + ::core::mem::size_of::<$ty>();
+            if end > size {
+                size = end;
+            }
+        )*
+        size
+    }};
+    (@align $($ty:ty),*) => {{
+        let mut align = 1usize;
+        $(
+            if ::core::mem::align_of::<$ty>() > align {
+                align = ::core::mem::align_of::<$ty>();
+            }
+        )*
+        align
+    }};
+    (@check $size:expr, $($offset:expr => $ty:ty [$access:ident]),*) => {{
+        //(offset, size, align, (readable, writable))
+        let regs: &[(usize, usize, usize, (bool, bool))] = &[$(($offset, ::core::mem::size_of::<$ty>(), ::core::mem::align_of::<$ty>(), $crate::__register_block!(@access $access))),*];
+        let mut idx = 0;
+        while idx < regs.len() {
+            let (offset, size, align, (readable, writable)) = regs[idx];
+            assert!(offset % align == 0, "register offset is not aligned for its type");
+            assert!(offset + size <= $size, "register does not fit within block");
+
+            let mut other = idx + 1;
+            while other < regs.len() {
+                let (other_offset, other_size, _, (other_readable, other_writable)) = regs[other];
+                let disjoint = offset + size <= other_offset || other_offset + other_size <= offset;
+                let shared = !(readable && other_readable) && !(writable && other_writable);
+                assert!(disjoint || shared, "registers overlap");
+                other += 1;
+            }
+            idx += 1;
+        }
+    }};
+    (@access ro) => { (true, false) };
+    (@access wo) => { (false, true) };
+    (@access rw) => { (true, true) };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::MemoryMap;
+
+    crate::mmio_registers! {
+        struct Regs {
+            ctrl: u8 @ 0x00,
+            data: crate::Le<u32> @ 0x04,
+        }
+    }
+
+    #[cfg(feature = "macros")]
+    crate::register_block! {
+        struct Block {
+            0x00 => status: u16 [ro],
+            0x04 => data / set_data: crate::Be<u32> [rw],
+        }
+    }
+
+    #[repr(C, align(8))]
+    struct Backing([u8; 16]);
+
+    #[test]
+    fn should_access_registers() {
+        let mut backing = Backing([0; 16]);
+        let mut regs = Regs::<crate::ReadWrite>::new(unsafe {
+            MemoryMap::from_ptr(backing.0.as_mut_ptr() as *mut [u8; 8])
+        });
+        regs.ctrl().write(1);
+        regs.data().write(0x1234_5678.into());
+        assert_eq!(regs.ctrl().read(), 1);
+        assert_eq!(regs.data().read().get(), 0x1234_5678);
+        assert_eq!(backing.0[4..8], [0x78, 0x56, 0x34, 0x12]);
+
+        assert_eq!(Regs::<crate::ReadWrite>::SIZE, 8);
+        assert_eq!(Regs::<crate::ReadWrite>::ALIGN, 4);
+        assert_eq!(regs.into_inner().as_ptr() as *const u8, backing.0.as_ptr());
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_reject_misaligned_map() {
+        let mut backing = Backing([0; 16]);
+        Regs::<crate::ReadWrite>::new(unsafe {
+            MemoryMap::from_ptr(backing.0.as_mut_ptr().add(1) as *mut [u8; 8])
+        });
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn should_access_block() {
+        let mut backing = Backing([0; 16]);
+        let mut block = Block::<crate::ReadWrite>::new(unsafe {
+            MemoryMap::from_ptr(backing.0.as_mut_ptr() as *mut [u8; 8])
+        });
+        block.set_data(0x1234_5678.into());
+        assert_eq!(block.data().get(), 0x1234_5678);
+        assert_eq!(block.status(), 0);
+        assert_eq!(backing.0[4..8], [0x12, 0x34, 0x56, 0x78]);
+
+        assert_eq!(Block::<crate::ReadWrite>::SIZE, 8);
+        assert_eq!(Block::<crate::ReadWrite>::ALIGN, 4);
+        assert_eq!(block.into_inner().as_ptr() as *const u8, backing.0.as_ptr());
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    #[should_panic]
+    fn should_reject_misaligned_block() {
+        let mut backing = Backing([0; 16]);
+        Block::<crate::ReadWrite>::new(unsafe {
+            MemoryMap::from_ptr(backing.0.as_mut_ptr().add(2) as *mut [u8; 8])
+        });
+    }
+}
@@ -0,0 +1,386 @@
+//! Single producer single consumer ring buffer
+
+use core::{fmt, marker, mem};
+use core::sync::atomic::Ordering;
+
+use crate::{volatile, Atomic, Error, ErrorKind, MemoryMapSlice};
+
+//Head and tail are kept on separate cache lines, so that producer and consumer do not contend on them
+const HEAD_OFFSET: usize = 0;
+const TAIL_OFFSET: usize = 64;
+const HEADER_SIZE: usize = 128;
+
+///Single producer single consumer ring buffer of `T` over memory region
+///
+///Region starts with header, holding indices of head and tail as atomic `u32`, followed by slots for elements.
+///State is kept entirely within region, hence ring buffer can be shared between processes by mapping the same file (or shared memory object)
+///with `MAP_SHARED`: one process pushes while other pops. Zeroed region is empty ring buffer.
+///
+///It is up to user to ensure that there is at most one producer and at most one consumer at any time,
+///across all processes. Within single process [split](#method.split) enforces it.
+///
+///One slot is always kept empty to tell full ring buffer from empty, hence capacity is one less than number of slots.
+///
+///## Usage
+///
+///```
+///use dev_mmio::{MemoryMapSlice, RingBuffer};
+///
+///let region = MemoryMapSlice::anonymous(4096).expect("to map");
+/////Anonymous memory is zeroed, which is valid u32
+///let mut ring = unsafe { RingBuffer::<u32>::new(region) }.expect("to fit");
+///let (mut producer, mut consumer) = ring.split();
+///
+///std::thread::scope(|scope| {
+///    scope.spawn(move || for idx in 0..10_000 {
+///        let mut val = idx;
+///        while let Err(rejected) = producer.push(val) {
+///            val = rejected;
+///            std::thread::yield_now();
+///        }
+///    });
+///
+///    let mut expected = 0;
+///    while expected < 10_000 {
+///        match consumer.pop() {
+///            Some(val) => {
+///                assert_eq!(val, expected);
+///                expected += 1;
+///            },
+///            None => std::thread::yield_now(),
+///        }
+///    }
+///});
+///
+///assert!(ring.is_empty());
+///```
+pub struct RingBuffer<T> {
+    region: MemoryMapSlice<u8>,
+    //Byte offset of first slot
+    data: usize,
+    //Number of slots
+    slots: u32,
+    _type: marker::PhantomData<T>,
+}
+
+impl<T: Copy> RingBuffer<T> {
+    ///Creates ring buffer over `region`, with capacity derived from its length.
+    ///
+    ///Region is not initialized, so that ring buffer created by other process can be attached to.
+    ///Use [clear](#method.clear) to initialize region, which is not zeroed.
+    ///
+    ///Returns `TooSmall` error if region does not fit header and at least two slots,
+    ///and `Misaligned` error if region is not aligned for `T` and `u32`.
+    ///
+    ///## Safety
+    ///
+    ///Every slot consumer can pop must hold valid `T`, which is only guaranteed for zeroed region of `T` valid when zeroed
+    ///or for region written by trusted producer of the same `T`.
+    ///Indices in header are kept within bounds regardless.
+    ///
+    ///## Panics
+    ///
+    ///If `T` is zero sized.
+    pub unsafe fn new(region: MemoryMapSlice<u8>) -> Result<Self, Error> {
+        assert!(mem::size_of::<T>() != 0, "zero sized type cannot be stored in ring buffer");
+
+        let align = mem::align_of::<T>().max(mem::align_of::<u32>());
+        if region.as_ptr() as usize & (align - 1) != 0 {
+            return Err(Error::new(ErrorKind::Misaligned, 0));
+        }
+
+        let data = HEADER_SIZE.next_multiple_of(mem::align_of::<T>());
+        let slots = match region.len().checked_sub(data) {
+            Some(len) => len / mem::size_of::<T>(),
+            None => 0,
+        };
+        if slots < 2 {
+            return Err(Error::new(ErrorKind::TooSmall, 0));
+        }
+
+        Ok(Self {
+            region,
+            data,
+            slots: u32::try_from(slots).unwrap_or(u32::MAX),
+            _type: marker::PhantomData,
+        })
+    }
+
+    #[inline]
+    ///Returns maximum number of elements.
+    pub const fn capacity(&self) -> usize {
+        self.slots as usize - 1
+    }
+
+    #[inline]
+    ///Returns number of elements, which is only a snapshot if ring buffer is accessed concurrently.
+    pub fn len(&self) -> usize {
+        let head = self.load(HEAD_OFFSET, Ordering::Acquire);
+        let tail = self.load(TAIL_OFFSET, Ordering::Acquire);
+        match tail >= head {
+            true => (tail - head) as usize,
+            false => (self.slots - head + tail) as usize,
+        }
+    }
+
+    #[inline]
+    ///Returns whether there are no elements, which is only a snapshot if ring buffer is accessed concurrently.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    ///Removes all elements, resetting head and tail.
+    ///
+    ///It must not be called while ring buffer is accessed by other process.
+    pub fn clear(&mut self) {
+        self.store(HEAD_OFFSET, 0, Ordering::Relaxed);
+        self.store(TAIL_OFFSET, 0, Ordering::Release);
+    }
+
+    #[inline]
+    ///Pushes `val` to the tail, returning it back if ring buffer is full.
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        unsafe {
+            self.push_unchecked(val)
+        }
+    }
+
+    #[inline]
+    ///Pops element from the head, if any.
+    pub fn pop(&mut self) -> Option<T> {
+        unsafe {
+            self.pop_unchecked()
+        }
+    }
+
+    #[inline]
+    ///Splits ring buffer into producer and consumer, which can be used from separate threads.
+    pub fn split(&mut self) -> (Producer<'_, T>, Consumer<'_, T>) {
+        let ring = &*self;
+        (Producer {
+            ring,
+        }, Consumer {
+            ring,
+        })
+    }
+
+    #[inline]
+    ///Returns underlying region.
+    pub fn into_inner(self) -> MemoryMapSlice<u8> {
+        self.region
+    }
+
+    #[inline(always)]
+    fn index_ptr(&self, offset: usize) -> *mut u32 {
+        unsafe {
+            (self.region.as_ptr() as *mut u8).add(offset) as *mut u32
+        }
+    }
+
+    #[inline(always)]
+    fn load(&self, offset: usize, order: Ordering) -> u32 {
+        let idx = unsafe {
+            u32::load(self.index_ptr(offset), order)
+        };
+        //Index written by other process is not trusted to be within bounds
+        idx % self.slots
+    }
+
+    #[inline(always)]
+    fn store(&self, offset: usize, idx: u32, order: Ordering) {
+        unsafe {
+            u32::store(self.index_ptr(offset), idx, order)
+        }
+    }
+
+    #[inline(always)]
+    fn slot(&self, idx: u32) -> *mut T {
+        unsafe {
+            (self.region.as_ptr() as *mut u8).add(self.data + idx as usize * mem::size_of::<T>()) as *mut T
+        }
+    }
+
+    #[inline(always)]
+    fn next(&self, idx: u32) -> u32 {
+        match idx + 1 {
+            next if next == self.slots => 0,
+            next => next,
+        }
+    }
+
+    //Caller must be the only producer
+    unsafe fn push_unchecked(&self, val: T) -> Result<(), T> {
+        let tail = self.load(TAIL_OFFSET, Ordering::Relaxed);
+        let next = self.next(tail);
+        if next == self.load(HEAD_OFFSET, Ordering::Acquire) {
+            return Err(val);
+        }
+
+        volatile::write(self.slot(tail), val);
+        self.store(TAIL_OFFSET, next, Ordering::Release);
+        Ok(())
+    }
+
+    //Caller must be the only consumer
+    unsafe fn pop_unchecked(&self) -> Option<T> {
+        let head = self.load(HEAD_OFFSET, Ordering::Relaxed);
+        if head == self.load(TAIL_OFFSET, Ordering::Acquire) {
+            return None;
+        }
+
+        let val = volatile::read(self.slot(head));
+        self.store(HEAD_OFFSET, self.next(head), Ordering::Release);
+        Some(val)
+    }
+}
+
+impl<T> fmt::Debug for RingBuffer<T> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RingBuffer").field("region", &self.region).field("slots", &self.slots).finish()
+    }
+}
+
+///Producer of [RingBuffer](struct.RingBuffer.html), pushing elements to its tail
+pub struct Producer<'a, T> {
+    ring: &'a RingBuffer<T>,
+}
+
+impl<T: Copy> Producer<'_, T> {
+    #[inline]
+    ///Pushes `val` to the tail, returning it back if ring buffer is full.
+    pub fn push(&mut self, val: T) -> Result<(), T> {
+        unsafe {
+            self.ring.push_unchecked(val)
+        }
+    }
+}
+
+///Consumer of [RingBuffer](struct.RingBuffer.html), popping elements from its head
+pub struct Consumer<'a, T> {
+    ring: &'a RingBuffer<T>,
+}
+
+impl<T: Copy> Consumer<'_, T> {
+    #[inline]
+    ///Pops element from the head, if any.
+    pub fn pop(&mut self) -> Option<T> {
+        unsafe {
+            self.ring.pop_unchecked()
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for Producer<'_, T> {
+}
+
+unsafe impl<T: Send> Send for Consumer<'_, T> {
+}
+
+#[cfg(all(test, feature = "mmap"))]
+mod tests {
+    use core::sync::atomic::Ordering;
+
+    use super::{RingBuffer, HEAD_OFFSET, TAIL_OFFSET};
+    use crate::{ErrorKind, MemoryMapSlice};
+
+    fn ring(len: usize) -> RingBuffer<u32> {
+        let region = MemoryMapSlice::anonymous(len).expect("to map");
+        unsafe {
+            RingBuffer::new(region).expect("to fit")
+        }
+    }
+
+    #[test]
+    fn should_reject_too_small_region() {
+        let region = MemoryMapSlice::anonymous(128).expect("to map");
+        let error = unsafe {
+            RingBuffer::<u32>::new(region).unwrap_err()
+        };
+        assert_eq!(error.kind(), ErrorKind::TooSmall);
+    }
+
+    #[test]
+    fn should_push_and_pop() {
+        let mut ring = ring(4096);
+        assert!(ring.is_empty());
+        assert_eq!(ring.capacity(), (4096 - 128) / 4 - 1);
+        assert_eq!(ring.pop(), None);
+
+        ring.push(1).expect("to push");
+        ring.push(2).expect("to push");
+        assert_eq!(ring.len(), 2);
+        assert_eq!(ring.pop(), Some(1));
+        assert_eq!(ring.pop(), Some(2));
+        assert_eq!(ring.pop(), None);
+    }
+
+    #[test]
+    fn should_reject_push_when_full() {
+        let mut ring = ring(4096);
+        for idx in 0..ring.capacity() as u32 {
+            ring.push(idx).expect("to push");
+        }
+
+        assert_eq!(ring.len(), ring.capacity());
+        assert_eq!(ring.push(u32::MAX), Err(u32::MAX));
+        assert_eq!(ring.pop(), Some(0));
+        ring.push(u32::MAX).expect("to push after pop");
+    }
+
+    #[test]
+    fn should_wrap_around() {
+        let mut ring = ring(4096);
+        let capacity = ring.capacity() as u32;
+        for idx in 0..capacity * 3 {
+            ring.push(idx).expect("to push");
+            assert_eq!(ring.pop(), Some(idx));
+            assert!(ring.is_empty());
+        }
+    }
+
+    #[test]
+    fn should_keep_corrupted_indices_in_bounds() {
+        let mut ring = ring(4096);
+        let slots = ring.slots;
+        ring.store(HEAD_OFFSET, u32::MAX, Ordering::Relaxed);
+        ring.store(TAIL_OFFSET, slots + 1, Ordering::Relaxed);
+
+        assert!(ring.len() <= ring.capacity());
+        while ring.pop().is_some() {
+        }
+        assert!(ring.is_empty());
+        ring.push(1).expect("to push");
+        assert_eq!(ring.pop(), Some(1));
+    }
+
+    #[test]
+    fn should_pass_elements_between_threads() {
+        let mut ring = ring(4096);
+        let (mut producer, mut consumer) = ring.split();
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || for idx in 0..100_000 {
+                let mut val = idx;
+                while let Err(rejected) = producer.push(val) {
+                    val = rejected;
+                    std::thread::yield_now();
+                }
+            });
+
+            let mut expected = 0;
+            while expected < 100_000 {
+                match consumer.pop() {
+                    Some(val) => {
+                        assert_eq!(val, expected);
+                        expected += 1;
+                    },
+                    None => std::thread::yield_now(),
+                }
+            }
+        });
+
+        assert!(ring.is_empty());
+    }
+}
@@ -0,0 +1,810 @@
+//! Memory mapped region of multiple elements
+
+use core::{fmt, ptr, mem, marker};
+
+#[cfg(feature = "mmap")]
+use crate::Error;
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+use crate::ErrorKind;
+use crate::{volatile, RawPtr};
+use crate::access::{self, ReadWrite};
+#[cfg(feature = "mmap")]
+use crate::access::ReadOnly;
+
+#[cfg(all(feature = "mmap", unix))]
+use crate::unix;
+#[cfg(all(feature = "mmap", windows))]
+use crate::win32;
+
+///Memory mapped IO region of `len` elements of `T`
+///
+///Compared to [MemoryMap](struct.MemoryMap.html) it allows to map whole peripheral block or buffer.
+///
+///Access `A` determines whether region can be read and/or written.
+pub struct MemoryMapSlice<T, A = ReadWrite> {
+    //Pointer to requested offset within mapping
+    ptr: *mut T,
+    //Number of elements
+    len: usize,
+    //Base address and length of mapping as returned by OS
+    base: *mut core::ffi::c_void,
+    map_len: usize,
+    //Flags of mapping, as only private anonymous memory can be resized by copying
+    #[cfg(all(feature = "mmap", unix))]
+    flags: libc::c_int,
+    #[cfg(all(feature = "mmap", windows))]
+    handle: win32::HANDLE,
+    _access: marker::PhantomData<A>,
+}
+
+impl<T, A: access::Readable> MemoryMapSlice<T, A> {
+    #[inline]
+    ///Reads element at `idx`
+    ///
+    ///## Panics
+    ///
+    ///If `idx` is out of bounds.
+    pub fn read_at(&self, idx: usize) -> T where T: Copy {
+        assert!(idx < self.len, "index {} is out of bounds for region of {} elements", idx, self.len);
+        unsafe {
+            self.read_at_unchecked(idx)
+        }
+    }
+
+    #[inline]
+    ///Reads element at `idx`, returning `None` if it is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<T> where T: Copy {
+        match idx < self.len {
+            true => Some(unsafe {
+                self.read_at_unchecked(idx)
+            }),
+            false => None,
+        }
+    }
+
+    #[inline]
+    ///Copies first `dst.len()` elements of region into `dst`, using volatile reads.
+    ///
+    ///## Panics
+    ///
+    ///If `dst` is longer than region.
+    pub fn copy_to_slice(&self, dst: &mut [T]) where T: Copy {
+        assert!(dst.len() <= self.len, "slice of {} elements is longer than region of {} elements", dst.len(), self.len);
+        for (idx, elem) in dst.iter_mut().enumerate() {
+            *elem = unsafe {
+                self.read_at_unchecked(idx)
+            };
+        }
+    }
+
+    #[inline]
+    ///Reads element at `idx` without bounds check.
+    ///
+    ///It does not require `T: Copy`, hence it is up to user to not duplicate ownership of any resources owned by `T`.
+    pub unsafe fn read_at_unchecked(&self, idx: usize) -> T {
+        volatile::read(self.ptr.add(idx))
+    }
+}
+
+impl<T, A: access::Readable> MemoryMapSlice<T, A> {
+    #[inline]
+    ///Returns iterator over elements, reading each of them as it is reached.
+    pub fn iter(&self) -> Iter<'_, T, A> {
+        Iter {
+            ptr: self.ptr,
+            idx: 0,
+            len: self.len,
+            _lifetime: marker::PhantomData,
+        }
+    }
+}
+
+impl<T, A: access::Writable> MemoryMapSlice<T, A> {
+    #[inline]
+    ///Writes element at `idx`
+    ///
+    ///## Panics
+    ///
+    ///If `idx` is out of bounds.
+    pub fn write_at(&mut self, idx: usize, val: T) {
+        assert!(idx < self.len, "index {} is out of bounds for region of {} elements", idx, self.len);
+        unsafe {
+            self.write_at_unchecked(idx, val)
+        }
+    }
+
+    #[inline]
+    ///Writes element at `idx`, returning `val` back if it is out of bounds.
+    pub fn set(&mut self, idx: usize, val: T) -> Result<(), T> {
+        match idx < self.len {
+            true => {
+                unsafe {
+                    self.write_at_unchecked(idx, val)
+                }
+                Ok(())
+            },
+            false => Err(val),
+        }
+    }
+
+    #[inline]
+    ///Copies `src` into first `src.len()` elements of region, using volatile writes.
+    ///
+    ///## Panics
+    ///
+    ///If `src` is longer than region.
+    pub fn copy_from_slice(&mut self, src: &[T]) where T: Copy {
+        assert!(src.len() <= self.len, "slice of {} elements is longer than region of {} elements", src.len(), self.len);
+        for (idx, elem) in src.iter().enumerate() {
+            unsafe {
+                self.write_at_unchecked(idx, *elem)
+            }
+        }
+    }
+
+    #[inline]
+    ///Writes `val` to every element, using volatile writes.
+    pub fn fill(&mut self, val: T) where T: Copy {
+        for idx in 0..self.len {
+            unsafe {
+                self.write_at_unchecked(idx, val)
+            }
+        }
+    }
+
+    #[inline]
+    ///Zeroes all elements, using volatile write of zeroed `T` per element.
+    ///
+    ///Same as [fill](#method.fill), every store is of `size_of::<T>()` bytes, as it may be required by device memory.
+    ///Unlike `memset`, which can be merged or elided by compiler, every element is written exactly once.
+    pub fn zero(&mut self) {
+        for idx in 0..self.len {
+            unsafe {
+                volatile::write(self.ptr.add(idx) as *mut mem::MaybeUninit<T>, mem::MaybeUninit::zeroed())
+            }
+        }
+    }
+
+    #[inline]
+    ///Writes `val` to every element with ordinary writes, which compiler is free to optimize.
+    ///
+    ///Intended for regions of normal memory, such as anonymous or shared memory.
+    ///It is not suitable for device memory, which must be written via [fill](#method.fill).
+    pub fn fill_plain(&mut self, val: T) where T: Copy {
+        for idx in 0..self.len {
+            unsafe {
+                ptr::write(self.ptr.add(idx), val)
+            }
+        }
+    }
+
+    #[inline]
+    ///Zeroes all elements via `memset`, which compiler is free to optimize.
+    ///
+    ///Intended for regions of normal memory, such as anonymous or shared memory.
+    ///It is not suitable for device memory, which must be zeroed via [zero](#method.zero).
+    pub fn zero_plain(&mut self) {
+        unsafe {
+            ptr::write_bytes(self.ptr, 0, self.len)
+        }
+    }
+
+    #[inline]
+    ///Writes element at `idx` without bounds check.
+    pub unsafe fn write_at_unchecked(&mut self, idx: usize, val: T) {
+        volatile::write(self.ptr.add(idx), val)
+    }
+}
+
+impl<T, A: access::Access> MemoryMapSlice<T, A> {
+    #[inline]
+    ///Creates region of `len` elements over already accessible memory at `ptr`.
+    ///
+    ///Nothing is unmapped on drop.
+    ///
+    ///It is up to user to ensure `ptr` is valid for access `A` to `len` elements for the lifetime of region.
+    pub const unsafe fn from_ptr(ptr: *mut T, len: usize) -> Self {
+        Self {
+            ptr,
+            len,
+            base: ptr::null_mut(),
+            map_len: mem::size_of::<T>() * len,
+            #[cfg(all(feature = "mmap", unix))]
+            flags: 0,
+            #[cfg(all(feature = "mmap", windows))]
+            handle: ptr::null_mut(),
+            _access: marker::PhantomData,
+        }
+    }
+
+    #[inline]
+    ///Returns iterator over pointers to elements, which can be used to access each element separately.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, A> {
+        IterMut {
+            ptr: self.ptr,
+            idx: 0,
+            len: self.len,
+            _lifetime: marker::PhantomData,
+        }
+    }
+
+    #[inline]
+    ///Returns number of elements within region.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    ///Returns whether region has no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    ///Returns pointer to first element.
+    pub const fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    #[inline]
+    ///Returns mutable pointer to first element.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr
+    }
+
+    #[cfg(all(feature = "mmap", not(windows)))]
+    #[allow(unused)]
+    #[inline]
+    ///Opens memory map of `len` elements.
+    ///
+    ///## Arguments
+    ///
+    ///- `offset` - Offset within memory to start.
+    ///- `fd` - File description. -1 for anonymous.
+    ///- `prot` - Memory protection. Specifies operations to be expected. At the very least must be `PROT_READ | PROT_WRITE`
+    ///- `flags` - Specifies whether changes to the mapping are visible across forks. Must be `MAP_ANON` for anonymous.
+    ///- `len` - Number of elements to map.
+    ///
+    ///`offset` is not required to be page aligned: mapping starts at page containing `offset`
+    ///and its length includes offset within this page, so that whole data is mapped.
+    ///But it must be aligned for `T`, otherwise `Misaligned` error is returned.
+    ///
+    ///It is up to user to ensure `prot` permits access `A`.
+    pub unsafe fn open_file_raw(offset: libc::off_t, fd: libc::c_int, prot: libc::c_int, flags: libc::c_int, len: usize) -> Result<Self, Error> {
+        #[cfg(unix)]
+        {
+            let size = mem::size_of::<T>().checked_mul(len).ok_or(Error::too_large())?;
+            let map = unix::map(unix::offset(offset)?, size, fd, prot, flags)?;
+
+            Self {
+                ptr: map.ptr as *mut _,
+                len,
+                base: map.base,
+                map_len: map.len,
+                flags,
+                _access: marker::PhantomData,
+            }.check_align()
+        }
+
+        #[cfg(not(unix))]
+        Err(Error::unsupported())
+    }
+
+    #[cfg(all(feature = "mmap", unix))]
+    ///Creates shared memory mapping of `len` elements of file at `path`
+    ///
+    ///File is closed once mapping is created.
+    ///
+    ///## Arguments
+    ///
+    ///- `path` - Path to file. Being `CStr` it is always NUL terminated.
+    ///- `offset` - Offset within file to start.
+    ///- `open_flags` - Flags to open file with. Must grant access required by `prot`.
+    ///- `prot` - Memory protection. It is up to user to ensure `prot` permits access `A`.
+    ///- `len` - Number of elements to map.
+    ///
+    ///Failure to open file is reported as `OpenFailed`, distinct from `MapFailed`.
+    pub unsafe fn open_path_raw(path: &core::ffi::CStr, offset: libc::off_t, open_flags: libc::c_int, prot: libc::c_int, len: usize) -> Result<Self, Error> {
+        unix::open_with(path, open_flags, |fd| Self::open_file_raw(offset, fd, prot, libc::MAP_SHARED, len))
+    }
+
+    #[cfg(all(feature = "mmap", windows))]
+    ///Opens memory map of `len` elements.
+    ///
+    ///## Arguments
+    ///
+    ///- `offset` - Offset within file to start.
+    ///- `handle` - File handle. `INVALID_HANDLE_VALUE` for memory backed by paging file.
+    ///- `protect` - Page protection of mapping object. At the very least must be `PAGE_READWRITE`
+    ///- `access` - Access to the view. At the very least must be `FILE_MAP_READ | FILE_MAP_WRITE`
+    ///- `len` - Number of elements to map.
+    ///
+    ///`offset` is not required to be aligned: view starts at allocation granularity boundary preceding `offset`
+    ///and its length includes offset within it, so that whole data is mapped.
+    ///But it must be aligned for `T`, otherwise `Misaligned` error is returned.
+    ///
+    ///It is up to user to ensure `protect` and `access` permit access `A`.
+    pub unsafe fn open_file_raw(offset: u64, handle: *mut core::ffi::c_void, protect: u32, access: u32, len: usize) -> Result<Self, Error> {
+        let size = mem::size_of::<T>().checked_mul(len).ok_or(Error::too_large())?;
+        let map = win32::map(offset, size, handle, protect, access)?;
+
+        Self {
+            ptr: map.ptr as *mut _,
+            len,
+            base: map.base,
+            map_len: map.len,
+            handle: map.handle,
+            _access: marker::PhantomData,
+        }.check_align()
+    }
+
+    #[cfg(feature = "mmap")]
+    #[inline]
+    fn check_align(self) -> Result<Self, Error> {
+        match self.ptr.is_aligned() {
+            true => Ok(self),
+            false => Err(Error::new(crate::ErrorKind::Misaligned, 0)),
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    ///Resizes region to `new_len` elements, preserving its content.
+    ///
+    ///Growing may move region, which invalidates any pointers to it, including `RawPtr` obtained previously.
+    ///
+    ///On linux it is `mremap`: shrinking is done in place and region of file mapping can only grow within file.
+    ///Shared anonymous region cannot grow, since its size is fixed at creation.
+    ///
+    ///On other unix platforms only private anonymous region can be resized: new one is mapped, content is copied into it and old region is unmapped.
+    ///
+    ///Returns `Unsupported` error when region cannot be resized as above, which is always the case on windows,
+    ///for region created by [from_ptr](#method.from_ptr) and when `new_len` is zero.
+    pub fn resize(&mut self, new_len: usize) -> Result<(), Error> {
+        if new_len == 0 || self.base.is_null() {
+            return Err(Error::unsupported());
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            //Object of shared anonymous memory keeps its size, so access past it after growing raises SIGBUS
+            let shared_anon = libc::MAP_SHARED | libc::MAP_ANONYMOUS;
+            if new_len > self.len && self.flags & shared_anon == shared_anon {
+                return Err(Error::unsupported());
+            }
+
+            let in_page = self.ptr as usize - self.base as usize;
+            let size = mem::size_of::<T>().checked_mul(new_len).ok_or(Error::too_large())?;
+            let map_len = in_page.checked_add(size).ok_or(Error::too_large())?;
+
+            let base = unsafe {
+                libc::mremap(self.base, self.map_len, map_len, libc::MREMAP_MAYMOVE)
+            };
+            if base == libc::MAP_FAILED {
+                return Err(Error::last(ErrorKind::ResizeFailed));
+            }
+
+            self.ptr = unsafe {
+                (base as *mut u8).add(in_page) as *mut T
+            };
+            self.len = new_len;
+            self.base = base;
+            self.map_len = map_len;
+            Ok(())
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            self.resize_copy(new_len)
+        }
+    }
+
+    #[cfg(all(feature = "mmap", any(not(target_os = "linux"), test)))]
+    //Maps new private anonymous region and moves content into it
+    fn resize_copy(&mut self, new_len: usize) -> Result<(), Error> {
+        #[cfg(unix)]
+        {
+            //Anything else is observed through file or by other processes, so it cannot be replaced by copy
+            let private_anon = libc::MAP_PRIVATE | libc::MAP_ANON;
+            if new_len == 0 || self.base.is_null() || self.flags & private_anon != private_anon {
+                return Err(Error::unsupported());
+            }
+
+            let new = unsafe {
+                MemoryMapSlice::<T>::open_file_raw(0, -1, libc::PROT_READ | libc::PROT_WRITE, private_anon, new_len)?
+            };
+            unsafe {
+                ptr::copy_nonoverlapping(self.ptr, new.ptr, core::cmp::min(self.len, new_len));
+            }
+            //Old region is unmapped on drop
+            let _ = mem::replace(self, unsafe { new.assume_access() });
+            Ok(())
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = new_len;
+            Err(Error::unsupported())
+        }
+    }
+
+    #[inline]
+    ///Changes access of memory map.
+    ///
+    ///It is up to user to ensure that underlying mapping permits access `B`.
+    pub unsafe fn assume_access<B: access::Access>(self) -> MemoryMapSlice<T, B> {
+        let result = MemoryMapSlice {
+            ptr: self.ptr,
+            len: self.len,
+            base: self.base,
+            map_len: self.map_len,
+            #[cfg(all(feature = "mmap", unix))]
+            flags: self.flags,
+            #[cfg(all(feature = "mmap", windows))]
+            handle: self.handle,
+            _access: marker::PhantomData,
+        };
+        mem::forget(self);
+        result
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<T> MemoryMapSlice<T> {
+    ///Creates anonymous memory mapping of `len` elements
+    pub fn anonymous(len: usize) -> Result<Self, Error> {
+        #[cfg(unix)]
+        unsafe {
+            Self::open_file_raw(0, -1, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_ANON | libc::MAP_SHARED, len)
+        }
+
+        #[cfg(windows)]
+        unsafe {
+            Self::open_file_raw(0, win32::INVALID_HANDLE_VALUE, win32::PAGE_READWRITE, win32::FILE_MAP_READ | win32::FILE_MAP_WRITE, len)
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        Err(Error::unsupported())
+    }
+
+    #[allow(unused)]
+    ///Creates memory mapping of `len` elements on `/dev/mem` which accesses physical memory
+    ///
+    ///Returns `Unsupported` error on macOS and other Apple platforms, which have no `/dev/mem`.
+    ///
+    ///## Arguments
+    ///
+    ///- `offset` - Offset within memory to start.
+    ///- `len` - Number of elements to map.
+    pub unsafe fn dev_mem(offset: libc::off_t, len: usize) -> Result<Self, Error> {
+        #[cfg(all(unix, not(target_vendor = "apple")))]
+        {
+            Self::open_path(c"/dev/mem", offset, len)
+        }
+
+        #[cfg(not(all(unix, not(target_vendor = "apple"))))]
+        Err(Error::unsupported())
+    }
+
+    #[cfg(unix)]
+    ///Creates shared memory mapping of `len` elements of file at `path`
+    ///
+    ///File is opened with `O_RDWR | O_SYNC | O_CLOEXEC` and closed once mapping is created.
+    ///
+    ///## Arguments
+    ///
+    ///- `path` - Path to file. Being `CStr` it is always NUL terminated.
+    ///- `offset` - Offset within file to start.
+    ///- `len` - Number of elements to map.
+    pub unsafe fn open_path(path: &core::ffi::CStr, offset: libc::off_t, len: usize) -> Result<Self, Error> {
+        Self::open_path_raw(path, offset, libc::O_RDWR | libc::O_SYNC | libc::O_CLOEXEC, libc::PROT_READ | libc::PROT_WRITE, len)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<T> MemoryMapSlice<T, ReadOnly> {
+    #[allow(unused)]
+    ///Creates read only memory mapping of `len` elements on `/dev/mem` which accesses physical memory
+    ///
+    ///Returns `Unsupported` error on macOS and other Apple platforms, which have no `/dev/mem`.
+    ///
+    ///## Arguments
+    ///
+    ///- `offset` - Offset within memory to start.
+    ///- `len` - Number of elements to map.
+    pub unsafe fn dev_mem_read_only(offset: libc::off_t, len: usize) -> Result<Self, Error> {
+        #[cfg(all(unix, not(target_vendor = "apple")))]
+        {
+            Self::open_path_raw(c"/dev/mem", offset, libc::O_RDONLY | libc::O_SYNC | libc::O_CLOEXEC, libc::PROT_READ, len)
+        }
+
+        #[cfg(not(all(unix, not(target_vendor = "apple"))))]
+        Err(Error::unsupported())
+    }
+}
+
+///Iterator over elements of [MemoryMapSlice](struct.MemoryMapSlice.html), reading them via volatile reads
+pub struct Iter<'a, T, A> {
+    ptr: *mut T,
+    idx: usize,
+    len: usize,
+    _lifetime: marker::PhantomData<&'a MemoryMapSlice<T, A>>,
+}
+
+impl<T: Copy, A: access::Readable> Iterator for Iter<'_, T, A> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx == self.len {
+            return None;
+        }
+
+        let result = unsafe {
+            volatile::read(self.ptr.add(self.idx))
+        };
+        self.idx += 1;
+        Some(result)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len - self.idx;
+        (len, Some(len))
+    }
+}
+
+impl<T: Copy, A: access::Readable> ExactSizeIterator for Iter<'_, T, A> {
+}
+
+///Iterator over pointers to elements of [MemoryMapSlice](struct.MemoryMapSlice.html)
+pub struct IterMut<'a, T, A> {
+    ptr: *mut T,
+    idx: usize,
+    len: usize,
+    _lifetime: marker::PhantomData<&'a mut MemoryMapSlice<T, A>>,
+}
+
+impl<'a, T, A> Iterator for IterMut<'a, T, A> {
+    type Item = RawPtr<'a, T, A>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx == self.len {
+            return None;
+        }
+
+        let result = RawPtr::new(unsafe {
+            self.ptr.add(self.idx)
+        });
+        self.idx += 1;
+        Some(result)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len - self.idx;
+        (len, Some(len))
+    }
+}
+
+impl<T, A> ExactSizeIterator for IterMut<'_, T, A> {
+}
+
+impl<T, A> Drop for MemoryMapSlice<T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.base.is_null() {
+            return;
+        }
+
+        #[cfg(all(feature = "mmap", unix))]
+        unsafe {
+            let result = libc::munmap(self.base, self.map_len);
+            debug_assert!(result == 0, "failed to unmap memory at {:p} of {} bytes", self.base, self.map_len);
+        }
+
+        #[cfg(all(feature = "mmap", windows))]
+        unsafe {
+            let result = win32::UnmapViewOfFile(self.base);
+            debug_assert!(result != 0, "failed to unmap memory at {:p}", self.base);
+            win32::CloseHandle(self.handle);
+        }
+    }
+}
+
+impl<T, A> fmt::Pointer for MemoryMapSlice<T, A> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.ptr, fmt)
+    }
+}
+
+impl<T, A> fmt::Debug for MemoryMapSlice<T, A> {
+    #[inline(always)]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("MemoryMapSlice").field("ptr", &self.ptr).field("len", &self.len).finish()
+    }
+}
+
+unsafe impl<T: Send, A> Send for MemoryMapSlice<T, A> {
+}
+
+unsafe impl<T: Sync, A> Sync for MemoryMapSlice<T, A> {
+}
+
+#[cfg(all(test, feature = "mmap"))]
+mod tests {
+    use super::MemoryMapSlice;
+
+    #[test]
+    fn should_read_and_write_each_element() {
+        let mut slice = MemoryMapSlice::<u32>::anonymous(16).expect("to map");
+        assert_eq!(slice.len(), 16);
+        for idx in 0..16 {
+            slice.write_at(idx, 0xdead_0000 | idx as u32);
+        }
+        for idx in 0..16 {
+            assert_eq!(slice.read_at(idx), 0xdead_0000 | idx as u32);
+            assert_eq!(slice.get(idx), Some(0xdead_0000 | idx as u32));
+        }
+        assert_eq!(slice.get(16), None);
+    }
+
+    #[test]
+    fn should_set_only_in_bounds() {
+        let mut slice = MemoryMapSlice::<u32>::anonymous(4).expect("to map");
+        assert_eq!(slice.set(0, 10), Ok(()));
+        assert_eq!(slice.set(3, 13), Ok(()));
+        assert_eq!(slice.set(4, 14), Err(14));
+        assert_eq!(slice.set(usize::MAX, 15), Err(15));
+        assert_eq!(slice.get(0), Some(10));
+        assert_eq!(slice.get(3), Some(13));
+        assert_eq!(slice.get(4), None);
+        assert_eq!(slice.get(usize::MAX), None);
+        assert_eq!(slice.iter().collect::<std::vec::Vec<_>>(), [10, 0, 0, 13]);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 16 is out of bounds for region of 16 elements")]
+    fn should_panic_on_read_out_of_bounds() {
+        let slice = MemoryMapSlice::<u32>::anonymous(16).expect("to map");
+        slice.read_at(16);
+    }
+
+    #[test]
+    fn should_zero_and_fill() {
+        let mut slice = MemoryMapSlice::<u16>::anonymous(8).expect("to map");
+        slice.fill(0xabcd);
+        assert!(slice.iter().all(|val| val == 0xabcd));
+        slice.zero();
+        assert!(slice.iter().all(|val| val == 0));
+    }
+
+    #[cfg(unix)]
+    fn check_resize(mut slice: MemoryMapSlice<u8>, resize: fn(&mut MemoryMapSlice<u8>, usize) -> Result<(), crate::Error>) {
+        let page = crate::page_size();
+        let len = slice.len();
+        (0..len).for_each(|idx| slice.write_at(idx, idx as u8));
+
+        resize(&mut slice, page * 2).expect("to grow");
+        assert_eq!(slice.len(), page * 2);
+        assert!(slice.iter().take(len).enumerate().all(|(idx, val)| val == idx as u8));
+        slice.write_at(page * 2 - 1, 0xaa);
+        assert_eq!(slice.read_at(page * 2 - 1), 0xaa);
+
+        assert_eq!(resize(&mut slice, 0).unwrap_err().kind(), crate::ErrorKind::Unsupported);
+        assert_eq!(slice.len(), page * 2);
+
+        resize(&mut slice, 16).expect("to shrink");
+        assert_eq!(slice.len(), 16);
+        assert!(slice.iter().enumerate().all(|(idx, val)| val == idx as u8));
+
+        let mut buffer = [0u8; 4];
+        let mut unmapped = unsafe {
+            MemoryMapSlice::<u8>::from_ptr(buffer.as_mut_ptr(), buffer.len())
+        };
+        assert_eq!(resize(&mut unmapped, 8).unwrap_err().kind(), crate::ErrorKind::Unsupported);
+    }
+
+    #[cfg(unix)]
+    fn private_anonymous(len: usize) -> MemoryMapSlice<u8> {
+        unsafe {
+            MemoryMapSlice::<u8>::open_file_raw(0, -1, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_ANON | libc::MAP_PRIVATE, len).expect("to map")
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_resize() {
+        check_resize(private_anonymous(crate::page_size() - 8), MemoryMapSlice::resize);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn should_resize_private_in_place() {
+        check_resize(private_anonymous(crate::page_size() - 8), |slice, new_len| {
+            let (base, len) = (slice.base, slice.len());
+            let result = slice.resize(new_len);
+            if new_len < len {
+                assert_eq!(slice.base, base);
+            }
+            result
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn should_resize_by_copy() {
+        check_resize(private_anonymous(crate::page_size() - 8), MemoryMapSlice::resize_copy);
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn should_not_grow_shared() {
+        let page = crate::page_size();
+        let mut slice = MemoryMapSlice::<u8>::anonymous(page).expect("to map");
+        slice.write_at(0, 1);
+        assert_eq!(slice.resize(page * 2).unwrap_err().kind(), crate::ErrorKind::Unsupported);
+        assert_eq!(slice.resize_copy(page * 2).unwrap_err().kind(), crate::ErrorKind::Unsupported);
+        assert_eq!(slice.len(), page);
+        assert_eq!(slice.read_at(0), 1);
+    }
+
+    #[cfg(all(feature = "std", unix))]
+    #[test]
+    fn should_not_copy_file_mapping() {
+        use std::os::unix::io::AsRawFd;
+
+        let file = crate::tests::TempFile::new("slice-resize-copy", 16);
+        let mut slice = unsafe {
+            MemoryMapSlice::<u8>::open_file_raw(0, file.file.as_raw_fd(), libc::PROT_READ | libc::PROT_WRITE, libc::MAP_PRIVATE, 8).expect("to map file")
+        };
+        assert_eq!(slice.resize_copy(16).unwrap_err().kind(), crate::ErrorKind::Unsupported);
+        assert_eq!(slice.len(), 8);
+    }
+
+    #[cfg(all(feature = "std", unix))]
+    #[test]
+    fn should_open_path() {
+        use std::io::Write;
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut file = crate::tests::TempFile::new("slice-open-path", 0);
+        file.file.write_all(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]).expect("to write file");
+        let path = std::ffi::CString::new(file.path.as_os_str().as_bytes()).expect("to convert path");
+
+        let mut slice = unsafe {
+            MemoryMapSlice::<u8>::open_path(&path, 4, 8).expect("to map file")
+        };
+        assert_eq!(slice.iter().collect::<std::vec::Vec<_>>(), [4, 5, 6, 7, 8, 9, 10, 11]);
+        slice.write_at(0, 40);
+
+        let slice = unsafe {
+            MemoryMapSlice::<u8, crate::ReadOnly>::open_path_raw(&path, 0, libc::O_RDONLY | libc::O_CLOEXEC, libc::PROT_READ, 8).expect("to map file")
+        };
+        assert_eq!(slice.read_at(4), 40);
+
+        let error = unsafe {
+            MemoryMapSlice::<u8>::open_path(c"/dev-mmio-missing-path", 0, 8).unwrap_err()
+        };
+        assert_eq!(error.kind(), crate::ErrorKind::OpenFailed);
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn should_zero_with_element_wide_stores() {
+        use crate::trace::tests::capture;
+        use crate::trace::Op;
+
+        let mut bytes = MemoryMapSlice::<u8>::anonymous(16).expect("to map");
+        bytes.fill(1);
+        let accesses = capture(bytes.as_ptr(), 16, || bytes.zero());
+        assert_eq!(accesses.len(), 16);
+        assert!(accesses.iter().enumerate().all(|(idx, access)| access.op == Op::Write && access.offset == idx && access.value::<u8>() == 0));
+
+        let mut words = MemoryMapSlice::<u16>::anonymous(8).expect("to map");
+        let accesses = capture(words.as_ptr() as *const u8, 16, || words.zero());
+        assert_eq!(accesses.len(), 8);
+        assert!(accesses.iter().enumerate().all(|(idx, access)| access.op == Op::Write && access.offset == idx * 2 && access.value::<u16>() == 0));
+    }
+}
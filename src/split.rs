@@ -0,0 +1,147 @@
+//! 64-bit registers accessed as pair of 32-bit halves
+//!
+//!Low half is expected at lower address and high half 4 bytes after it, as is common for `LO`/`HI` register pairs,
+//!regardless of byte order of CPU.
+
+use crate::{access, volatile, MemoryMap};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Order in which halves of 64-bit register are written
+pub enum SplitOrder {
+    ///Low half first, then high half
+    LoHi,
+    ///High half first, then low half
+    HiLo,
+}
+
+//Index of half in units of `u32`
+const LO: usize = 0;
+const HI: usize = 1;
+
+impl<A> MemoryMap<u64, A> {
+    #[inline(always)]
+    fn read_half(&self, half: usize) -> u32 {
+        #[cfg(feature = "mock")]
+        if let Some(mock) = self.mock.as_ref() {
+            return mock.read_at(half * 4);
+        }
+
+        unsafe {
+            volatile::read((self.ptr.as_ptr() as *mut u32).add(half))
+        }
+    }
+
+    #[inline(always)]
+    fn write_half(&self, half: usize, val: u32) {
+        #[cfg(feature = "mock")]
+        if let Some(mock) = self.mock.as_ref() {
+            return mock.write_at(half * 4, val);
+        }
+
+        unsafe {
+            volatile::write((self.ptr.as_ptr() as *mut u32).add(half), val)
+        }
+    }
+}
+
+impl<A: access::Readable> MemoryMap<u64, A> {
+    #[inline]
+    ///Reads low half, then high half, each with 32-bit volatile read.
+    ///
+    ///Intended for buses without 64-bit access.
+    ///Result is torn if register changes between reads, unless device latches high half on read of low half.
+    ///Use [read_split_hi_lo_hi](#method.read_split_hi_lo_hi) for free running counters.
+    pub fn read_split_lo_hi(&self) -> u64 {
+        let lo = self.read_half(LO);
+        let hi = self.read_half(HI);
+        (hi as u64) << 32 | lo as u64
+    }
+
+    #[inline]
+    ///Reads high half, low half and high half again, retrying until both reads of high half are equal.
+    ///
+    ///Intended for free running counters on buses without 64-bit access, as it handles carry into high half between reads.
+    pub fn read_split_hi_lo_hi(&self) -> u64 {
+        let mut high = self.read_half(HI);
+        loop {
+            let low = self.read_half(LO);
+            match self.read_half(HI) {
+                next if next == high => break (high as u64) << 32 | low as u64,
+                next => high = next,
+            }
+        }
+    }
+}
+
+impl<A: access::Writable> MemoryMap<u64, A> {
+    #[inline]
+    ///Writes `val` in halves with 32-bit volatile writes, in `order` required by device.
+    ///
+    ///Intended for buses without 64-bit access.
+    pub fn write_split(&mut self, val: u64, order: SplitOrder) {
+        match order {
+            SplitOrder::LoHi => {
+                self.write_half(LO, val as u32);
+                self.write_half(HI, (val >> 32) as u32);
+            },
+            SplitOrder::HiLo => {
+                self.write_half(HI, (val >> 32) as u32);
+                self.write_half(LO, val as u32);
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "mock")]
+    #[test]
+    fn should_read_counter_across_rollover() {
+        use crate::MemoryMap;
+
+        let (map, handle) = MemoryMap::<u64, crate::ReadOnly>::mock(0xffff_fffe);
+        //Free running counter, incremented on every read
+        handle.on_read(|val| val + 1);
+        assert_eq!(map.read_split_hi_lo_hi(), 0x1_0000_0002);
+        assert_eq!(handle.accesses().len(), 5);
+
+        let (map, handle) = MemoryMap::<u64, crate::ReadOnly>::mock(0xffff_fffe);
+        handle.on_read(|val| val + 1);
+        //Carry between reads tears value
+        assert_eq!(map.read_split_lo_hi(), 0x1_ffff_ffff);
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn should_record_split_write() {
+        use crate::mock::Access;
+        use super::SplitOrder;
+
+        let (mut map, handle) = crate::MemoryMap::<u64>::mock(0);
+        map.write_split(0x1111_1111_2222_2222, SplitOrder::HiLo);
+        assert_eq!(handle.accesses(), [Access::Write(0x1111_1111_0000_0000), Access::Write(0x1111_1111_2222_2222)]);
+
+        handle.clear();
+        map.write_split(0x3333_3333_4444_4444, SplitOrder::LoHi);
+        assert_eq!(handle.accesses(), [Access::Write(0x1111_1111_4444_4444), Access::Write(0x3333_3333_4444_4444)]);
+        assert_eq!(map.read_split_lo_hi(), 0x3333_3333_4444_4444);
+    }
+
+    #[cfg(all(feature = "trace", feature = "mmap"))]
+    #[test]
+    fn should_trace_halves() {
+        use crate::trace::tests::capture;
+        use crate::trace::Op;
+        use super::SplitOrder;
+
+        let mut map = crate::MemoryMap::<u64>::try_anonymous().expect("to map");
+        let ptr = map.as_ptr() as *const u8;
+        let accesses = capture(ptr, 8, || map.write_split(0x1111_1111_2222_2222, SplitOrder::HiLo));
+        assert_eq!(accesses.len(), 2);
+        assert!(accesses[0].op == Op::Write && accesses[0].offset == 4 && accesses[0].value::<u32>() == 0x1111_1111);
+        assert!(accesses[1].op == Op::Write && accesses[1].offset == 0 && accesses[1].value::<u32>() == 0x2222_2222);
+
+        let accesses = capture(ptr, 8, || assert_eq!(map.read_split_lo_hi(), 0x1111_1111_2222_2222));
+        assert_eq!(accesses.iter().map(|access| (access.op == Op::Read, access.offset, access.len())).collect::<std::vec::Vec<_>>(), [(true, 0, 4), (true, 4, 4)]);
+    }
+}
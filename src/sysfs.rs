@@ -0,0 +1,80 @@
+//! Linux sysfs helpers
+
+use core::{fmt, str};
+use core::fmt::Write;
+
+use crate::{Error, ErrorKind};
+
+///NUL terminated path
+pub struct Path {
+    buf: [u8; 96],
+    len: usize,
+}
+
+impl Path {
+    ///Formats path, which must fit capacity alongside NUL.
+    pub fn new(path: fmt::Arguments<'_>) -> Self {
+        let mut result = Self {
+            buf: [0; 96],
+            len: 0,
+        };
+        //Capacity fits longest path, with NUL remaining in place
+        let _ = result.write_fmt(path);
+        result
+    }
+
+    #[inline]
+    pub fn as_ptr(&self) -> *const libc::c_char {
+        self.buf.as_ptr() as _
+    }
+}
+
+impl fmt::Write for Path {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let end = self.len + text.len();
+        //Leave space for NUL
+        if end >= self.buf.len() {
+            return Err(fmt::Error);
+        }
+
+        self.buf[self.len..end].copy_from_slice(text.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+///Opens file, telling missing file and missing permission apart from other failures.
+pub unsafe fn open(path: &Path, flags: libc::c_int) -> Result<libc::c_int, Error> {
+    let fd = libc::open(path.as_ptr(), flags | libc::O_CLOEXEC);
+    if fd == -1 {
+        let error = Error::last(ErrorKind::OpenFailed);
+        return Err(match error.raw_os_error() {
+            libc::ENOENT => Error::new(ErrorKind::NotFound, libc::ENOENT),
+            code @ (libc::EACCES | libc::EPERM) => Error::new(ErrorKind::PermissionDenied, code),
+            _ => error,
+        });
+    }
+
+    Ok(fd)
+}
+
+///Reads content of attribute file into `buf`.
+pub fn read<'a>(path: &Path, buf: &'a mut [u8]) -> Result<&'a str, Error> {
+    let len = unsafe {
+        let fd = open(path, libc::O_RDONLY)?;
+        let result = match libc::read(fd, buf.as_mut_ptr() as _, buf.len()) {
+            -1 => Err(Error::last(ErrorKind::InvalidAttribute)),
+            len => Ok(len as usize),
+        };
+        libc::close(fd);
+        result?
+    };
+
+    str::from_utf8(&buf[..len]).map_err(|_| Error::new(ErrorKind::InvalidAttribute, libc::EINVAL))
+}
+
+#[inline]
+///Parses `0x` prefixed hex number.
+pub fn parse_hex(text: &str) -> Option<u64> {
+    u64::from_str_radix(text.trim().strip_prefix("0x")?, 16).ok()
+}
@@ -0,0 +1,204 @@
+//! Tracing of memory accesses
+//!
+//!Global hook set by [set_hook](fn.set_hook.html) is called on every volatile access made via memory maps,
+//!slices and pointers of this crate, after read or before write.
+//!Bytewise accesses (e.g. `read_unaligned` or `fill`) are reported byte by byte.
+//!Memory created by `MemoryMap::mock` is not traced, as it records accesses on its own.
+//!
+//!Hook runs in whatever context access happens, which may be interrupt handler or critical section,
+//!hence it must not block or access memory maps itself.
+
+use core::{mem, ptr};
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Kind of access
+pub enum Op {
+    ///Memory is read
+    Read,
+    ///Memory is written
+    Write,
+}
+
+///Hook, accepting kind of access, address, pointer to bytes read or written and number of them.
+///
+///Bytes are valid for reads only for the duration of call. Accesses of integers, which are all accesses of registers,
+///have every byte initialized, while accesses of other types may include uninitialized padding
+///(e.g. `read_maybe_uninit` or `#[repr(C)]` structs), hence it is up to hook to read bytes only when it knows they are initialized.
+///
+///```
+///use dev_mmio::trace::{self, Op};
+///
+///fn hook(op: Op, addr: usize, bytes: *const u8, len: usize) {
+///    //Only integer registers are accessed
+///    let bytes = unsafe {
+///        core::slice::from_raw_parts(bytes, len)
+///    };
+///    println!("{:?} {:#x}: {:02x?}", op, addr, bytes);
+///}
+///
+///trace::set_hook(Some(hook));
+///```
+pub type Hook = fn(Op, usize, *const u8, usize);
+
+static HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+///Sets global hook, returning previous one.
+///
+///`None` disables tracing.
+pub fn set_hook(hook: Option<Hook>) -> Option<Hook> {
+    let hook = match hook {
+        Some(hook) => hook as *mut (),
+        None => ptr::null_mut(),
+    };
+
+    let prev = HOOK.swap(hook, Ordering::AcqRel);
+    match prev.is_null() {
+        true => None,
+        false => Some(unsafe {
+            mem::transmute::<*mut (), Hook>(prev)
+        }),
+    }
+}
+
+#[inline]
+pub(crate) fn trace<T>(op: Op, ptr: *const T, val: &T) {
+    let hook = HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        unsafe {
+            let hook = mem::transmute::<*mut (), Hook>(hook);
+            hook(op, ptr as usize, val as *const T as *const u8, mem::size_of::<T>());
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use core::mem::{self, MaybeUninit};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    use super::{set_hook, Op};
+    use crate::MemoryMap;
+
+    #[derive(Debug)]
+    ///Traced access
+    pub(crate) struct Traced {
+        pub(crate) op: Op,
+        ///Offset relative to start of captured range
+        pub(crate) offset: usize,
+        bytes: Vec<MaybeUninit<u8>>,
+    }
+
+    impl Traced {
+        ///Returns width of access in bytes.
+        pub(crate) fn len(&self) -> usize {
+            self.bytes.len()
+        }
+
+        ///Returns value of access, which must be of integer `T`.
+        pub(crate) fn value<T: crate::Bits>(&self) -> T {
+            assert_eq!(self.bytes.len(), mem::size_of::<T>());
+            //Integer accesses have every byte initialized
+            unsafe {
+                (self.bytes.as_ptr() as *const T).read_unaligned()
+            }
+        }
+    }
+
+    //Hook is global, hence tests capturing accesses run one at a time
+    static LOCK: Mutex<()> = Mutex::new(());
+    static RECORDS: Mutex<Vec<Traced>> = Mutex::new(Vec::new());
+    //Only accesses within range are recorded, as other tests access memory concurrently
+    static START: AtomicUsize = AtomicUsize::new(0);
+    static END: AtomicUsize = AtomicUsize::new(0);
+
+    fn record(op: Op, addr: usize, bytes: *const u8, len: usize) {
+        let start = START.load(Ordering::Acquire);
+        if addr >= start && addr < END.load(Ordering::Acquire) {
+            let mut copy = Vec::with_capacity(len);
+            unsafe {
+                core::ptr::copy_nonoverlapping(bytes as *const MaybeUninit<u8>, copy.as_mut_ptr(), len);
+                copy.set_len(len);
+            }
+            RECORDS.lock().unwrap_or_else(|error| error.into_inner()).push(Traced {
+                op,
+                offset: addr - start,
+                bytes: copy,
+            });
+        }
+    }
+
+    ///Runs `cb`, returning accesses within `len` bytes at `start`.
+    pub(crate) fn capture<F: FnOnce()>(start: *const u8, len: usize, cb: F) -> Vec<Traced> {
+        let _lock = LOCK.lock().unwrap_or_else(|error| error.into_inner());
+        START.store(start as usize, Ordering::Release);
+        END.store(start as usize + len, Ordering::Release);
+        set_hook(Some(record));
+        cb();
+        set_hook(None);
+        END.store(0, Ordering::Release);
+        mem::take(&mut *RECORDS.lock().unwrap_or_else(|error| error.into_inner()))
+    }
+
+    #[test]
+    fn should_trace_read_and_write() {
+        let mut data = 0u32;
+        let mut map = unsafe {
+            MemoryMap::<u32>::from_ptr(&mut data)
+        };
+        let accesses = capture(map.as_ptr() as *const u8, 4, || {
+            map.write(0x1234_5678);
+            assert_eq!(map.read(), 0x1234_5678);
+        });
+
+        assert_eq!(accesses.len(), 2);
+        assert_eq!((accesses[0].op, accesses[0].offset, accesses[0].value::<u32>()), (Op::Write, 0, 0x1234_5678));
+        assert_eq!((accesses[1].op, accesses[1].offset, accesses[1].value::<u32>()), (Op::Read, 0, 0x1234_5678));
+    }
+
+    #[test]
+    fn should_trace_padded_struct_without_reading_padding() {
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        struct Padded {
+            flag: u8,
+            value: u32,
+        }
+
+        let mut data = Padded {
+            flag: 0,
+            value: 0,
+        };
+        let mut map = unsafe {
+            MemoryMap::<Padded>::from_ptr(&mut data)
+        };
+        let accesses = capture(map.as_ptr() as *const u8, mem::size_of::<Padded>(), || {
+            map.write(Padded {
+                flag: 1,
+                value: 2,
+            });
+            let val = map.read();
+            assert_eq!((val.flag, val.value), (1, 2));
+        });
+
+        assert_eq!(accesses.iter().map(|access| (access.op, access.len())).collect::<Vec<_>>(), [(Op::Write, 8), (Op::Read, 8)]);
+    }
+
+    #[test]
+    fn should_trace_only_captured_range() {
+        let mut data = [0u32; 2];
+        let (mut map, mut other) = unsafe {
+            (MemoryMap::<u32>::from_ptr(&mut data[0]), MemoryMap::<u32>::from_ptr(&mut data[1]))
+        };
+        let accesses = capture(map.as_ptr() as *const u8, 4, || {
+            other.write(1);
+            map.write(2);
+        });
+        other.write(3);
+
+        assert_eq!(accesses.len(), 1);
+        assert_eq!(accesses[0].value::<u32>(), 2);
+    }
+}
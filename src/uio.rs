@@ -0,0 +1,84 @@
+//! Linux userspace IO (UIO) devices
+//!
+//!Maps memory regions of `/dev/uioX` devices, described by `/sys/class/uio/uioX/maps/mapN`.
+
+use crate::{unix, Error, ErrorKind, MemoryMap};
+use crate::sysfs::{self, Path};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///Memory region of UIO device, as described by sysfs
+pub struct UioMap {
+    ///Physical address of region
+    pub addr: u64,
+    ///Size of region in bytes
+    pub size: u64,
+    ///Offset of region within its first page
+    pub offset: u64,
+}
+
+//Reads hex attribute of map
+fn read_attr(device: u32, map: u32, name: &str) -> Result<u64, Error> {
+    let path = Path::new(format_args!("/sys/class/uio/uio{}/maps/map{}/{}", device, map, name));
+    let mut buf = [0u8; 32];
+    let text = sysfs::read(&path, &mut buf).map_err(|error| match error.kind() {
+        ErrorKind::NotFound => error,
+        _ => Error::new(ErrorKind::InvalidAttribute, error.raw_os_error()),
+    })?;
+    sysfs::parse_hex(text).ok_or(Error::new(ErrorKind::InvalidAttribute, libc::EINVAL))
+}
+
+///Returns description of memory region `map` of device `/dev/uio{device}`.
+///
+///Fails with `NotFound` if there is no such device or region, and with `InvalidAttribute` if its attributes cannot be read.
+pub fn map_info(device: u32, map: u32) -> Result<UioMap, Error> {
+    Ok(UioMap {
+        addr: read_attr(device, map, "addr")?,
+        size: read_attr(device, map, "size")?,
+        offset: read_attr(device, map, "offset")?,
+    })
+}
+
+impl<T> MemoryMap<T> {
+    ///Creates memory mapping of region `map` of UIO device `/dev/uio{device}`
+    ///
+    ///Region is selected by offset of `map` pages, as required by UIO, and data starts at region's offset within its page.
+    ///Use [map_info](uio/fn.map_info.html) to find physical address of region.
+    ///
+    ///Device is retained by memory map and accessible via [raw_fd](#method.raw_fd), so that interrupts can be waited on by reading it.
+    ///
+    ///Errors:
+    ///
+    ///- `NotFound` if there is no such device or region.
+    ///- `InvalidAttribute` if sysfs attributes of region cannot be read.
+    ///- `PermissionDenied` if user lacks permission to open device.
+    ///- `TooSmall` if `size_of::<T>()` exceeds size of region.
+    pub unsafe fn uio(device: u32, map: u32) -> Result<Self, Error> {
+        let info = map_info(device, map)?;
+        if info.size < core::mem::size_of::<T>() as u64 {
+            return Err(Error::new(ErrorKind::TooSmall, 0));
+        }
+
+        let page = unix::granularity();
+        let in_page = info.offset as usize & (page - 1);
+        let path = Path::new(format_args!("/dev/uio{}", device));
+        let fd = sysfs::open(&path, libc::O_RDWR)?;
+
+        let map = unix::map(map as u64 * page as u64, in_page + core::mem::size_of::<T>(), fd, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED);
+        let map = match map {
+            Ok(map) => unix::Mapping {
+                ptr: map.ptr.add(in_page),
+                ..map
+            },
+            Err(error) => {
+                libc::close(fd);
+                return Err(error);
+            },
+        };
+
+        let mut result = Self::from_mapping(map).inspect_err(|_| {
+            libc::close(fd);
+        })?;
+        result.fd = fd;
+        Ok(result)
+    }
+}
@@ -0,0 +1,224 @@
+//! Unix specifics
+
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Error, ErrorKind};
+
+pub const ENOMEM: libc::c_int = libc::ENOMEM;
+#[cfg(target_os = "macos")]
+///Passed in place of file descriptor of anonymous mapping to request 2 MiB superpages
+pub const VM_FLAGS_SUPERPAGE_SIZE_2MB: libc::c_int = 2 << 16;
+
+static PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+#[inline]
+///Returns page size, which mapping offset must be multiple of.
+///
+///It is queried once and cached afterwards.
+pub fn granularity() -> usize {
+    match PAGE_SIZE.load(Ordering::Relaxed) {
+        0 => {
+            let size = unsafe {
+                libc::sysconf(libc::_SC_PAGESIZE) as usize
+            };
+            PAGE_SIZE.store(size, Ordering::Relaxed);
+            size
+        },
+        size => size,
+    }
+}
+
+///Memory mapping
+pub struct Mapping {
+    ///Base address returned by `mmap`
+    pub base: *mut libc::c_void,
+    ///Length passed to `mmap`
+    pub len: usize,
+    ///Address of requested offset within mapping
+    pub ptr: *mut u8,
+    ///Requested offset
+    pub offset: u64,
+    ///Protection passed to `mmap`
+    pub prot: libc::c_int,
+    ///Flags passed to `mmap`
+    pub flags: libc::c_int,
+    ///Size of pages backing mapping
+    pub page: usize,
+}
+
+///Opens file at `path` with `flags` to be mapped by `map`, closing it afterwards regardless of result.
+///
+///Failure to open file is reported as `OpenFailed`.
+pub unsafe fn open_with<R, F: FnOnce(libc::c_int) -> Result<R, Error>>(path: &core::ffi::CStr, flags: libc::c_int, map: F) -> Result<R, Error> {
+    let fd = libc::open(path.as_ptr(), flags);
+    if fd == -1 {
+        return Err(Error::last(ErrorKind::OpenFailed));
+    }
+
+    let result = map(fd);
+    libc::close(fd);
+    result
+}
+
+///Converts `off_t` offset to 64-bit one, as accepted by `map`.
+pub fn offset(offset: libc::off_t) -> Result<u64, Error> {
+    u64::try_from(offset).map_err(|_| Error::new(ErrorKind::MapFailed, libc::EINVAL))
+}
+
+#[inline]
+///Maps `size` bytes starting at `offset`, rounding it down to page boundary.
+///
+///Where `off_t` is 32-bit, `mmap64` is used to reach offsets above 4 GiB.
+pub unsafe fn map(offset: u64, size: usize, fd: libc::c_int, prot: libc::c_int, flags: libc::c_int) -> Result<Mapping, Error> {
+    map_at(ptr::null_mut(), offset, size, fd, prot, flags, granularity())
+}
+
+///Same as `map`, but passes `addr` to `mmap` as address of mapping and rounds offset down to multiple of `page`,
+///which is size of pages backing mapping.
+///
+///Zero `size` maps nothing, returning null base and dangling pointer, aligned to page size.
+pub unsafe fn map_at(addr: *mut libc::c_void, offset: u64, size: usize, fd: libc::c_int, prot: libc::c_int, flags: libc::c_int, page: usize) -> Result<Mapping, Error> {
+    if size == 0 {
+        return Ok(Mapping {
+            base: ptr::null_mut(),
+            len: 0,
+            ptr: ptr::without_provenance_mut(granularity()),
+            offset,
+            prot,
+            flags,
+            page,
+        });
+    }
+
+    #[cfg(any(all(target_os = "linux", target_env = "gnu"), target_os = "android"))]
+    use libc::{mmap64 as mmap, off64_t as off_t};
+    #[cfg(not(any(all(target_os = "linux", target_env = "gnu"), target_os = "android")))]
+    use libc::{mmap, off_t};
+
+    let offset_mask = (page - 1) as u64;
+    let in_page = (offset & offset_mask) as usize;
+    let len = in_page + size;
+    let page_offset = off_t::try_from(offset & !offset_mask).map_err(|_| Error::new(ErrorKind::MapFailed, libc::EOVERFLOW))?;
+
+    let base = mmap(addr, len, prot, flags, fd, page_offset);
+    if base == libc::MAP_FAILED {
+        return Err(Error::last(ErrorKind::MapFailed));
+    }
+
+    Ok(Mapping {
+        base,
+        len,
+        ptr: (base as *mut u8).add(in_page),
+        offset,
+        prot,
+        flags,
+        page,
+    })
+}
+
+///Makes instruction cache coherent with data written to `len` bytes at `start`.
+///
+///Same as `__builtin___clear_cache`, it is no-op on `x86` where caches are always coherent.
+pub unsafe fn clear_cache(start: *const u8, len: usize) -> Result<(), Error> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        let _ = (start, len);
+        Ok(())
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        use core::arch::asm;
+
+        let ctr: u64;
+        asm!("mrs {}, ctr_el0", out(reg) ctr, options(nomem, nostack));
+        let start = start as usize;
+        let end = start + len;
+
+        //Clean data cache lines to point of unification, unless it is not required (IDC)
+        if ctr & (1 << 28) == 0 {
+            let line = 4usize << ((ctr >> 16) & 0xf);
+            let mut addr = start & !(line - 1);
+            while addr < end {
+                asm!("dc cvau, {}", in(reg) addr, options(nostack));
+                addr += line;
+            }
+        }
+        asm!("dsb ish", options(nostack));
+
+        //Invalidate instruction cache lines, unless it is not required (DIC)
+        if ctr & (1 << 29) == 0 {
+            let line = 4usize << (ctr & 0xf);
+            let mut addr = start & !(line - 1);
+            while addr < end {
+                asm!("ic ivau, {}", in(reg) addr, options(nostack));
+                addr += line;
+            }
+            asm!("dsb ish", options(nostack));
+        }
+        asm!("isb", options(nostack));
+        Ok(())
+    }
+
+    #[cfg(all(target_arch = "arm", any(target_os = "linux", target_os = "android")))]
+    {
+        //__ARM_NR_cacheflush
+        const CACHEFLUSH: libc::c_long = 0x0f0002;
+        match libc::syscall(CACHEFLUSH, start, start.add(len), 0) {
+            -1 => Err(Error::last(ErrorKind::ProtectFailed)),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64",
+                  all(target_arch = "arm", any(target_os = "linux", target_os = "android")))))]
+    {
+        let _ = (start, len);
+        Err(Error::unsupported())
+    }
+}
+
+#[inline]
+///Returns last error.
+pub fn errno() -> libc::c_int {
+    unsafe {
+        #[cfg(any(target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos", target_os = "freebsd"))]
+        {
+            *libc::__error()
+        }
+        #[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+        {
+            *libc::__errno()
+        }
+        #[cfg(any(target_os = "solaris", target_os = "illumos"))]
+        {
+            *libc::___errno()
+        }
+        #[cfg(target_os = "haiku")]
+        {
+            *libc::_errnop()
+        }
+        #[cfg(not(any(target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos", target_os = "freebsd",
+                      target_os = "android", target_os = "netbsd", target_os = "openbsd", target_os = "solaris", target_os = "illumos", target_os = "haiku")))]
+        {
+            *libc::__errno_location()
+        }
+    }
+}
+
+///Returns description of common error codes.
+pub fn describe(code: libc::c_int) -> Option<&'static str> {
+    match code {
+        libc::EPERM => Some("Operation not permitted"),
+        libc::ENOENT => Some("No such file or directory"),
+        libc::EBADF => Some("Bad file descriptor"),
+        libc::ENOMEM => Some("Cannot allocate memory"),
+        libc::EACCES => Some("Permission denied"),
+        libc::EEXIST => Some("File exists"),
+        libc::ENODEV => Some("No such device"),
+        libc::EINVAL => Some("Invalid argument"),
+        libc::EOVERFLOW => Some("Value too large for defined data type"),
+        _ => None,
+    }
+}
@@ -0,0 +1,20 @@
+//! Volatile access
+
+use core::ptr;
+
+#[inline(always)]
+///Reads `ptr` with volatile read, tracing it if enabled.
+pub unsafe fn read<T>(ptr: *const T) -> T {
+    let val = ptr::read_volatile(ptr);
+    #[cfg(feature = "trace")]
+    crate::trace::trace(crate::trace::Op::Read, ptr, &val);
+    val
+}
+
+#[inline(always)]
+///Writes `ptr` with volatile write, tracing it if enabled.
+pub unsafe fn write<T>(ptr: *mut T, val: T) {
+    #[cfg(feature = "trace")]
+    crate::trace::trace(crate::trace::Op::Write, ptr, &val);
+    ptr::write_volatile(ptr, val)
+}
@@ -0,0 +1,195 @@
+//! Windows API bindings
+
+#![allow(non_snake_case, non_camel_case_types)]
+
+use core::{ffi, mem, ptr};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Error, ErrorKind};
+
+pub type HANDLE = *mut ffi::c_void;
+
+pub const INVALID_HANDLE_VALUE: HANDLE = -1isize as HANDLE;
+pub const PAGE_NOACCESS: u32 = 0x01;
+pub const PAGE_READONLY: u32 = 0x02;
+pub const PAGE_READWRITE: u32 = 0x04;
+pub const PAGE_WRITECOPY: u32 = 0x08;
+pub const PAGE_EXECUTE: u32 = 0x10;
+pub const PAGE_EXECUTE_READ: u32 = 0x20;
+pub const PAGE_EXECUTE_READWRITE: u32 = 0x40;
+pub const PAGE_EXECUTE_WRITECOPY: u32 = 0x80;
+pub const FILE_MAP_COPY: u32 = 0x01;
+pub const FILE_MAP_WRITE: u32 = 0x02;
+pub const FILE_MAP_READ: u32 = 0x04;
+pub const FILE_MAP_EXECUTE: u32 = 0x20;
+
+pub const ERROR_FILE_NOT_FOUND: u32 = 2;
+pub const ERROR_PATH_NOT_FOUND: u32 = 3;
+pub const ERROR_ACCESS_DENIED: u32 = 5;
+pub const ERROR_INVALID_HANDLE: u32 = 6;
+pub const ERROR_NOT_ENOUGH_MEMORY: u32 = 8;
+pub const ERROR_INVALID_PARAMETER: u32 = 87;
+
+pub const ENOMEM: ffi::c_int = ERROR_NOT_ENOUGH_MEMORY as ffi::c_int;
+
+#[repr(C)]
+pub struct SYSTEM_INFO {
+    pub wProcessorArchitecture: u16,
+    pub wReserved: u16,
+    pub dwPageSize: u32,
+    pub lpMinimumApplicationAddress: *mut ffi::c_void,
+    pub lpMaximumApplicationAddress: *mut ffi::c_void,
+    pub dwActiveProcessorMask: usize,
+    pub dwNumberOfProcessors: u32,
+    pub dwProcessorType: u32,
+    pub dwAllocationGranularity: u32,
+    pub wProcessorLevel: u16,
+    pub wProcessorRevision: u16,
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    pub fn GetSystemInfo(info: *mut SYSTEM_INFO);
+    pub fn CreateFileMappingW(file: HANDLE, attrs: *mut ffi::c_void, protect: u32, max_size_high: u32, max_size_low: u32, name: *const u16) -> HANDLE;
+    pub fn MapViewOfFileEx(mapping: HANDLE, access: u32, offset_high: u32, offset_low: u32, size: usize, base: *mut ffi::c_void) -> *mut ffi::c_void;
+    pub fn UnmapViewOfFile(base: *const ffi::c_void) -> i32;
+    pub fn FlushViewOfFile(base: *const ffi::c_void, size: usize) -> i32;
+    pub fn VirtualProtect(base: *mut ffi::c_void, size: usize, protect: u32, old_protect: *mut u32) -> i32;
+    pub fn FlushInstructionCache(process: HANDLE, base: *const ffi::c_void, size: usize) -> i32;
+    pub fn GetCurrentProcess() -> HANDLE;
+    pub fn VirtualLock(base: *mut ffi::c_void, size: usize) -> i32;
+    pub fn VirtualUnlock(base: *mut ffi::c_void, size: usize) -> i32;
+    pub fn CloseHandle(handle: HANDLE) -> i32;
+    pub fn GetLastError() -> u32;
+}
+
+static GRANULARITY: AtomicUsize = AtomicUsize::new(0);
+static PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+//Queries system info once, caching both values
+fn system_info(cache: &AtomicUsize) -> usize {
+    match cache.load(Ordering::Relaxed) {
+        0 => {
+            let info = unsafe {
+                let mut info = mem::MaybeUninit::<SYSTEM_INFO>::uninit();
+                GetSystemInfo(info.as_mut_ptr());
+                info.assume_init()
+            };
+            GRANULARITY.store(info.dwAllocationGranularity as usize, Ordering::Relaxed);
+            PAGE_SIZE.store(info.dwPageSize as usize, Ordering::Relaxed);
+            cache.load(Ordering::Relaxed)
+        },
+        value => value,
+    }
+}
+
+#[inline]
+///Returns allocation granularity, which mapping offset must be multiple of.
+pub fn granularity() -> usize {
+    system_info(&GRANULARITY)
+}
+
+#[inline]
+///Returns page size.
+pub fn page_size() -> usize {
+    system_info(&PAGE_SIZE)
+}
+
+///Memory mapping
+pub struct Mapping {
+    ///Base address returned by `MapViewOfFileEx`
+    pub base: *mut ffi::c_void,
+    ///Length passed to `MapViewOfFileEx`
+    pub len: usize,
+    ///Address of requested offset within mapping
+    pub ptr: *mut u8,
+    ///Requested offset
+    pub offset: u64,
+    ///Mapping object
+    pub handle: HANDLE,
+    ///Page protection of mapping object
+    pub protect: u32,
+    ///Access of view
+    pub access: u32,
+    ///Size of pages backing mapping
+    pub page: usize,
+}
+
+#[inline]
+///Maps `size` bytes starting at `offset`, rounding it down to allocation granularity.
+pub unsafe fn map(offset: u64, size: usize, handle: HANDLE, protect: u32, access: u32) -> Result<Mapping, Error> {
+    map_at(ptr::null_mut(), offset, size, handle, protect, access)
+}
+
+///Same as `map`, but places view at `addr` unless it is null.
+///
+///Zero `size` maps nothing, returning null base and dangling pointer, aligned to page size.
+pub unsafe fn map_at(addr: *mut ffi::c_void, offset: u64, size: usize, handle: HANDLE, protect: u32, access: u32) -> Result<Mapping, Error> {
+    if size == 0 {
+        return Ok(Mapping {
+            base: ptr::null_mut(),
+            len: 0,
+            ptr: ptr::without_provenance_mut(page_size()),
+            offset,
+            handle: ptr::null_mut(),
+            protect,
+            access,
+            page: page_size(),
+        });
+    }
+
+    let offset_mask = (granularity() - 1) as u64;
+    let view_offset = offset & !offset_mask;
+    let in_view = (offset & offset_mask) as usize;
+    let len = in_view + size;
+
+    //Paging file has no size of its own, so mapping object must be given one
+    let max_size = match handle == INVALID_HANDLE_VALUE {
+        true => view_offset + len as u64,
+        false => 0,
+    };
+
+    let mapping = CreateFileMappingW(handle, ptr::null_mut(), protect, (max_size >> 32) as u32, max_size as u32, ptr::null());
+    if mapping.is_null() {
+        return Err(Error::last(ErrorKind::MapFailed));
+    }
+
+    let base = MapViewOfFileEx(mapping, access, (view_offset >> 32) as u32, view_offset as u32, len, addr);
+    if base.is_null() {
+        let error = Error::last(ErrorKind::MapFailed);
+        CloseHandle(mapping);
+        return Err(error);
+    }
+
+    Ok(Mapping {
+        base,
+        len,
+        ptr: (base as *mut u8).add(in_view),
+        offset,
+        handle: mapping,
+        protect,
+        access,
+        page: page_size(),
+    })
+}
+
+#[inline]
+///Returns last error.
+pub fn errno() -> ffi::c_int {
+    unsafe {
+        GetLastError() as ffi::c_int
+    }
+}
+
+///Returns description of common error codes.
+pub fn describe(code: ffi::c_int) -> Option<&'static str> {
+    match code as u32 {
+        ERROR_FILE_NOT_FOUND => Some("The system cannot find the file specified"),
+        ERROR_PATH_NOT_FOUND => Some("The system cannot find the path specified"),
+        ERROR_ACCESS_DENIED => Some("Access is denied"),
+        ERROR_INVALID_HANDLE => Some("The handle is invalid"),
+        ERROR_NOT_ENOUGH_MEMORY => Some("Not enough memory resources are available to process this command"),
+        ERROR_INVALID_PARAMETER => Some("The parameter is incorrect"),
+        _ => None,
+    }
+}